@@ -0,0 +1,60 @@
+//! Throughput of the structural scanner backends against each other, and of
+//! `decode_with`, which takes strings whole where the scan found them,
+//! against `decode_into`, which parses without it. Run with
+//! `cargo bench --bench structural`.
+
+extern crate json_flex;
+
+use std::time::Instant;
+
+use json_flex::{decode_into, decode_with, structural_index_with, JFObjectBuilder, Options,
+                ScanBackend};
+
+fn sample(records: usize) -> String {
+    let mut text = String::from("[");
+    for i in 0..records {
+        if i > 0 {
+            text.push(',');
+        }
+        text.push_str(&format!("{{\"id\": {}, \"name\": \"user \\\"{}\\\"\", \"score\": {}.5, \
+                                 \"tags\": [\"a\", \"b\", \"c\"], \"bio\": \"{}\"}}",
+                               i,
+                               i,
+                               i % 100,
+                               "lorem ipsum dolor sit amet ".repeat(i % 7)));
+    }
+    text.push(']');
+    text
+}
+
+fn report(name: &str, bytes: usize, rounds: u32, f: &mut dyn FnMut()) {
+    f();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        f();
+    }
+    let seconds = start.elapsed().as_secs_f64() / rounds as f64;
+    println!("{:<10} {:>8.1} MB/s", name, bytes as f64 / seconds / 1e6);
+}
+
+fn main() {
+    let text = sample(100_000);
+    let options = Options::default();
+    println!("{} bytes", text.len());
+    let backends = [ScanBackend::Scalar, ScanBackend::Portable, ScanBackend::Sse2, ScanBackend::Avx2];
+    for &backend in &backends {
+        if !backend.is_available() {
+            println!("{:<10} unavailable", format!("{:?}", backend));
+            continue;
+        }
+        report(&format!("{:?}", backend), text.len(), 10, &mut || {
+            structural_index_with(&text, &options, backend).unwrap();
+        });
+    }
+    report("unscanned", text.len(), 2, &mut || {
+        decode_into(&text.clone(), &options, JFObjectBuilder::new(&options)).unwrap();
+    });
+    report("scanned", text.len(), 2, &mut || {
+        decode_with(text.clone(), &options).unwrap();
+    });
+}
@@ -0,0 +1,216 @@
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+use error::{DecodeError, ErrorKind};
+use json_flex::JFObject;
+use limits::Limit;
+use options::Options;
+use parser::decode_with;
+
+/// Bytes asked of the reader at a time by `decode_async`.
+const CHUNK: usize = 8 * 1024;
+
+/// Reads `reader` to the end and decodes it as `decode_with` would. With
+/// `max_input_bytes` set, reading stops as soon as the input is too long.
+pub fn decode_async<R>(reader: R, options: &Options) -> DecodeAsync<R>
+    where R: AsyncRead + Unpin
+{
+    DecodeAsync {
+        reader,
+        options: options.clone(),
+        buffer: Vec::new(),
+    }
+}
+
+/// The future returned by `decode_async`.
+pub struct DecodeAsync<R> {
+    reader: R,
+    options: Options,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> Future for DecodeAsync<R> {
+    type Output = Result<Box<JFObject>, DecodeError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut chunk = [0; CHUNK];
+        loop {
+            let mut read = ReadBuf::new(&mut chunk);
+            if let Err(e) = ready!(Pin::new(&mut this.reader).poll_read(cx, &mut read)) {
+                return Poll::Ready(Err(io_error(e, &this.buffer)));
+            }
+            if read.filled().is_empty() {
+                break;
+            }
+            this.buffer.extend_from_slice(read.filled());
+            let max = this.options.limits.max_input_bytes;
+            if max.is_some_and(|max| this.buffer.len() > max) {
+                let kind = ErrorKind::LimitExceeded(Limit::InputBytes);
+                return Poll::Ready(Err(DecodeError::new(kind, "", 0)));
+            }
+        }
+        let bytes = mem::take(&mut this.buffer);
+        Poll::Ready(match String::from_utf8(bytes) {
+            Ok(text) => decode_with(text, &this.options),
+            Err(e) => Err(utf8_error(e.as_bytes(), e.utf8_error().valid_up_to())),
+        })
+    }
+}
+
+/// Decodes each non-blank line of `reader` as its own document, as
+/// `decode_ndjson_parallel` does, while the lines arrive. Error positions
+/// count from the start of the input; a line longer than `max_input_bytes`
+/// is an error without being buffered, and a read error ends the stream.
+pub fn ndjson_stream<R>(reader: R, options: &Options) -> NdjsonStream<R>
+    where R: AsyncBufRead + Unpin
+{
+    NdjsonStream {
+        reader,
+        options: options.clone(),
+        line: Vec::new(),
+        line_number: 1,
+        offset: 0,
+        skipped: 0,
+        done: false,
+    }
+}
+
+/// The stream returned by `ndjson_stream`, yielding one result per line.
+pub struct NdjsonStream<R> {
+    reader: R,
+    options: Options,
+    /// The part of the current line read so far.
+    line: Vec<u8>,
+    line_number: usize,
+    /// Offset of the current line in the input.
+    offset: usize,
+    /// Bytes of the current line dropped for being over the limit.
+    skipped: usize,
+    done: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> NdjsonStream<R> {
+    /// The next result, for `while let Some(v) = stream.next_value().await`.
+    pub fn next_value(&mut self) -> NextValue<'_, R> {
+        NextValue { stream: self }
+    }
+
+    fn line_start(&self) -> (usize, usize) {
+        (self.line_number, self.offset)
+    }
+
+    /// Takes the current line, returning its result unless it is blank.
+    fn end_line(&mut self, length: usize) -> Option<Result<JFObject, DecodeError>> {
+        let line = mem::take(&mut self.line);
+        let at = self.line_start();
+        self.line_number += 1;
+        self.offset += length + self.skipped;
+        if mem::replace(&mut self.skipped, 0) > 0 {
+            let kind = ErrorKind::LimitExceeded(Limit::InputBytes);
+            return Some(Err(relocate(DecodeError::new(kind, "", 0), at)));
+        }
+        let text = match String::from_utf8(line) {
+            Ok(text) => text,
+            Err(e) => {
+                let error = utf8_error(e.as_bytes(), e.utf8_error().valid_up_to());
+                return Some(Err(relocate(error, at)));
+            }
+        };
+        if text.trim().is_empty() {
+            return None;
+        }
+        Some(decode_with(text, &self.options).map(|v| *v).map_err(|e| relocate(e, at)))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for NdjsonStream<R> {
+    type Item = Result<JFObject, DecodeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.done {
+            let (consumed, newline) = {
+                let available = match ready!(Pin::new(&mut this.reader).poll_fill_buf(cx)) {
+                    Ok(available) => available,
+                    Err(e) => {
+                        this.done = true;
+                        let error = io_error(e, &this.line);
+                        return Poll::Ready(Some(Err(relocate(error, this.line_start()))));
+                    }
+                };
+                if available.is_empty() {
+                    this.done = true;
+                    let length = this.line.len();
+                    return Poll::Ready(this.end_line(length));
+                }
+                let newline = available.iter().position(|&b| b == b'\n');
+                let consumed = newline.map_or(available.len(), |i| i + 1);
+                if this.skipped > 0 {
+                    this.skipped += consumed;
+                } else {
+                    this.line.extend_from_slice(&available[..consumed]);
+                }
+                (consumed, newline.is_some())
+            };
+            Pin::new(&mut this.reader).consume(consumed);
+
+            // The terminator does not count, nor a `\r` that may be the start
+            // of one.
+            let content = this.line.strip_suffix(b"\n").unwrap_or(&this.line);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+            let max = this.options.limits.max_input_bytes;
+            if max.is_some_and(|max| content.len() > max) {
+                this.skipped = this.line.len();
+                this.line.clear();
+            }
+            if newline {
+                let length = this.line.len();
+                this.line.pop();
+                if this.line.last() == Some(&b'\r') {
+                    this.line.pop();
+                }
+                if let Some(result) = this.end_line(length) {
+                    return Poll::Ready(Some(result));
+                }
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+/// The future returned by `NdjsonStream::next_value`.
+pub struct NextValue<'a, R: 'a> {
+    stream: &'a mut NdjsonStream<R>,
+}
+
+impl<'a, R: AsyncBufRead + Unpin> Future for NextValue<'a, R> {
+    type Output = Option<Result<JFObject, DecodeError>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Pin::new(&mut *self.get_mut().stream).poll_next(cx)
+    }
+}
+
+/// `error`, met after reading `bytes`.
+fn io_error(error: io::Error, bytes: &[u8]) -> DecodeError {
+    let text = String::from_utf8_lossy(bytes);
+    DecodeError::new(ErrorKind::Io(error.kind()), &text, text.len())
+}
+
+fn utf8_error(bytes: &[u8], valid_up_to: usize) -> DecodeError {
+    DecodeError::new(ErrorKind::InvalidUtf8, &String::from_utf8_lossy(bytes), valid_up_to)
+}
+
+/// `error`, found in a line on its own, with its position in the input.
+fn relocate(mut error: DecodeError, (line, offset): (usize, usize)) -> DecodeError {
+    error.position.line += line - 1;
+    error.position.offset += offset;
+    error
+}
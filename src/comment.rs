@@ -0,0 +1,247 @@
+use error::{DecodeError, Position};
+use json_flex::{quote, JFObject};
+use options::Options;
+use parser::{Event, Parser};
+use path::PathSegment;
+use visitor::build;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    /// `// ...` up to the end of the line.
+    Line,
+    /// `/* ... */`
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// On the lines before the node.
+    Before,
+    /// After the node, on the line where it ends when the source had it there.
+    After,
+    /// Inside an empty container, or after its last element when that element
+    /// could not take it.
+    Inside,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The comment as written, delimiters included.
+    pub text: String,
+    pub kind: CommentKind,
+    pub position: Position,
+    /// The node the comment is attached to; empty for the root value.
+    pub path: Vec<PathSegment>,
+    pub placement: Placement,
+}
+
+struct Level {
+    next_index: usize,
+    previous: Option<(Vec<PathSegment>, usize)>,
+}
+
+/// Assigns the comments skipped before each event to a neighbouring node.
+struct Attacher {
+    comments: Vec<Comment>,
+    path: Vec<PathSegment>,
+    levels: Vec<Level>,
+    key: Option<String>,
+}
+
+impl Attacher {
+    fn new() -> Attacher {
+        Attacher {
+            comments: Vec::new(),
+            path: Vec::new(),
+            levels: vec![Level {
+                             next_index: 0,
+                             previous: None,
+                         }],
+            key: None,
+        }
+    }
+
+    fn observe(&mut self, parser: &mut Parser, event: Option<&Event>) {
+        let next = match event {
+            Some(&Event::EndArray) |
+            Some(&Event::EndObject) |
+            None => None,
+            Some(Event::Key(k)) => Some(self.child(PathSegment::Key(k.to_string()))),
+            Some(_) => Some(self.next_path()),
+        };
+
+        // A comment between a key and its value belongs to that value.
+        let after_key = self.key.is_some() && next.is_some();
+        let text = parser.text();
+        for (start, end) in parser.take_comments() {
+            let previous = self.levels.last().unwrap().previous.clone();
+            let (path, placement) = match (previous, next.clone()) {
+                (Some((path, prev_end)), _) if !after_key &&
+                                                !text[prev_end..start].contains('\n') => {
+                    (path, Placement::After)
+                }
+                (_, Some(path)) => (path, Placement::Before),
+                (Some((path, _)), None) => (path, Placement::After),
+                (None, None) => (self.path.clone(), Placement::Inside),
+            };
+            let raw = &text[start..end];
+            self.comments.push(Comment {
+                text: raw.to_owned(),
+                kind: if raw.starts_with("//") {
+                    CommentKind::Line
+                } else {
+                    CommentKind::Block
+                },
+                position: Position::from_offset(text, start),
+                path,
+                placement,
+            });
+        }
+
+        match event {
+            Some(Event::Key(k)) => self.key = Some(k.to_string()),
+            Some(&Event::StartArray) |
+            Some(&Event::StartObject) => {
+                self.path = self.next_path();
+                self.key = None;
+                self.levels.push(Level {
+                    next_index: 0,
+                    previous: None,
+                });
+            }
+            Some(&Event::EndArray) |
+            Some(&Event::EndObject) => {
+                self.levels.pop();
+                let path = self.path.clone();
+                self.path.pop();
+                self.finish(path, parser.offset());
+            }
+            Some(_) => {
+                let path = self.next_path();
+                self.finish(path, parser.offset());
+            }
+            None => {}
+        }
+    }
+
+    fn child(&self, segment: PathSegment) -> Vec<PathSegment> {
+        let mut path = self.path.clone();
+        path.push(segment);
+        path
+    }
+
+    /// Path of the value the next value event will produce.
+    fn next_path(&self) -> Vec<PathSegment> {
+        if self.levels.len() == 1 {
+            return Vec::new();
+        }
+        match self.key {
+            Some(ref k) => self.child(PathSegment::Key(k.clone())),
+            None => self.child(PathSegment::Index(self.levels.last().unwrap().next_index)),
+        }
+    }
+
+    fn finish(&mut self, path: Vec<PathSegment>, end: usize) {
+        self.key = None;
+        let level = self.levels.last_mut().unwrap();
+        level.next_index += 1;
+        level.previous = Some((path, end));
+    }
+}
+
+/// Like `decode_with`, additionally returning every comment together with
+/// the node it belongs to.
+pub fn decode_with_comments(text: String,
+                            options: &Options)
+                            -> Result<(Box<JFObject>, Vec<Comment>), DecodeError> {
+    let mut parser = Parser::new(&text, options.clone());
+    parser.retain_comments();
+    let mut attacher = Attacher::new();
+    let value = build(&mut parser, |parser, event| attacher.observe(parser, event))?;
+    Ok((Box::new(value), attacher.comments))
+}
+
+impl JFObject {
+    /// Indented JSON with `comments` written back next to their nodes.
+    pub fn to_jsonc(&self, comments: &[Comment]) -> String {
+        let mut out = String::new();
+        let mut path = Vec::new();
+        write_comments(&mut out, comments, &path, Placement::Before, 0);
+        write_value(&mut out, self, comments, &mut path, 0);
+        for c in comments.iter().filter(|c| c.path.is_empty() && c.placement == Placement::After) {
+            out.push(' ');
+            out.push_str(&c.text);
+        }
+        out
+    }
+}
+
+fn write_comments(out: &mut String,
+                  comments: &[Comment],
+                  path: &[PathSegment],
+                  placement: Placement,
+                  depth: usize) {
+    for c in comments.iter().filter(|c| c.path == path && c.placement == placement) {
+        indent(out, depth);
+        out.push_str(&c.text);
+        out.push('\n');
+    }
+}
+
+fn write_value(out: &mut String,
+               value: &JFObject,
+               comments: &[Comment],
+               path: &mut Vec<PathSegment>,
+               depth: usize) {
+    let children: Vec<(PathSegment, &JFObject)> = match *value {
+        JFObject::Array(ref v) => {
+            v.iter().enumerate().map(|(i, v)| (PathSegment::Index(i), v)).collect()
+        }
+        JFObject::Dictionary(ref v) => {
+            v.iter().map(|(k, v)| (PathSegment::Key(k.clone()), v)).collect()
+        }
+        _ => {
+            out.push_str(&value.to_json());
+            return;
+        }
+    };
+    let (open, close) = if value.is_array() { ('[', ']') } else { ('{', '}') };
+    let inside = comments.iter().any(|c| c.path == *path && c.placement == Placement::Inside);
+    if children.is_empty() && !inside {
+        out.push(open);
+        out.push(close);
+        return;
+    }
+
+    out.push(open);
+    out.push('\n');
+    let count = children.len();
+    for (i, (segment, child)) in children.into_iter().enumerate() {
+        path.push(segment);
+        write_comments(out, comments, path, Placement::Before, depth + 1);
+        indent(out, depth + 1);
+        if let Some(PathSegment::Key(k)) = path.last() {
+            out.push_str(&quote(k));
+            out.push_str(": ");
+        }
+        write_value(out, child, comments, path, depth + 1);
+        if i + 1 < count {
+            out.push(',');
+        }
+        for c in comments.iter().filter(|c| c.path == *path && c.placement == Placement::After) {
+            out.push(' ');
+            out.push_str(&c.text);
+        }
+        out.push('\n');
+        path.pop();
+    }
+    write_comments(out, comments, path, Placement::Inside, depth + 1);
+    indent(out, depth);
+    out.push(close);
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("    ");
+    }
+}
@@ -0,0 +1,301 @@
+use std::fmt;
+use std::ops::Range;
+
+use error::DecodeError;
+use json_flex::{quote, JFObject};
+use options::Options;
+use parser::{decode_with, Event, Parser};
+use path::PathSegment;
+use visitor::build;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CstKind {
+    Object,
+    Array,
+    Scalar,
+}
+
+/// A value in a `Cst`, located by byte ranges into its text. Whitespace,
+/// comments and punctuation between nodes are not modelled; they stay in the
+/// text untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstNode {
+    pub kind: CstKind,
+    /// The value as written, brackets included.
+    pub range: Range<usize>,
+    /// The key as written, quotes included, for object members.
+    pub key_range: Option<Range<usize>>,
+    pub key: Option<String>,
+    pub children: Vec<CstNode>,
+}
+
+impl CstNode {
+    /// Where the member starts: its key, or the value in an array.
+    fn start(&self) -> usize {
+        self.key_range.as_ref().map_or(self.range.start, |r| r.start)
+    }
+
+    fn child_index(&self, segment: &PathSegment) -> Option<usize> {
+        match (self.kind, segment) {
+            (CstKind::Array, PathSegment::Index(i)) if *i < self.children.len() => Some(*i),
+            (CstKind::Object, PathSegment::Key(k)) => {
+                self.children.iter().rposition(|c| c.key.as_ref() == Some(k))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Source text together with the syntax tree over it, for editing values
+/// while keeping formatting, comments, key order and number spelling.
+/// Everything outside an edited value stays byte-identical.
+#[derive(Debug, Clone)]
+pub struct Cst {
+    text: String,
+    options: Options,
+    root: CstNode,
+}
+
+impl Cst {
+    pub fn parse(text: String, options: &Options) -> Result<Cst, DecodeError> {
+        let root = parse_nodes(&text, options)?;
+        Ok(Cst {
+            text,
+            options: options.clone(),
+            root,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    pub fn root(&self) -> &CstNode {
+        &self.root
+    }
+
+    pub fn get(&self, path: &[PathSegment]) -> Option<&CstNode> {
+        let mut node = &self.root;
+        for segment in path {
+            node = &node.children[node.child_index(segment)?];
+        }
+        Some(node)
+    }
+
+    /// The value at `path` as written in the source.
+    pub fn source(&self, path: &[PathSegment]) -> Option<&str> {
+        self.get(path).map(|node| &self.text[node.range.clone()])
+    }
+
+    pub fn to_jfobject(&self) -> JFObject {
+        let mut parser = Parser::new(&self.text, self.options.clone());
+        build(&mut parser, |_, _| {}).unwrap_or(JFObject::Null)
+    }
+
+    /// Replaces the value at `path`; `false` if there is none.
+    pub fn set(&mut self, path: &[PathSegment], value: &JFObject) -> bool {
+        match self.get(path).map(|node| node.range.clone()) {
+            Some(range) => self.splice(range, &to_source(value)).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Replaces the value at `path` with `source` as written, such as `1.10`
+    /// or a formatted object. Fails if `source` is not a single value.
+    pub fn set_raw(&mut self, path: &[PathSegment], source: &str) -> Result<bool, DecodeError> {
+        decode_with(source.to_owned(), &self.options)?;
+        match self.get(path).map(|node| node.range.clone()) {
+            Some(range) => self.splice(range, source).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Sets `key` in the object at `path`, adding it after the last member
+    /// with the same indentation when it is new.
+    pub fn insert(&mut self, path: &[PathSegment], key: &str, value: &JFObject) -> bool {
+        let object = match self.get(path) {
+            Some(node) if node.kind == CstKind::Object => node.clone(),
+            _ => return false,
+        };
+        let mut member = path.to_vec();
+        member.push(PathSegment::Key(key.to_owned()));
+        if self.get(&member).is_some() {
+            return self.set(&member, value);
+        }
+        let source = format!("{}: {}", quote(key), to_source(value));
+        self.append(&object, &source)
+    }
+
+    /// Appends `value` to the array at `path`.
+    pub fn push(&mut self, path: &[PathSegment], value: &JFObject) -> bool {
+        match self.get(path) {
+            Some(node) if node.kind == CstKind::Array => {
+                let array = node.clone();
+                self.append(&array, &to_source(value))
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes the element or member at `path` together with its comma.
+    pub fn remove(&mut self, path: &[PathSegment]) -> bool {
+        let (last, parent_path) = match path.split_last() {
+            Some(split) => split,
+            None => return false,
+        };
+        let parent = match self.get(parent_path) {
+            Some(parent) => parent,
+            None => return false,
+        };
+        let i = match parent.child_index(last) {
+            Some(i) => i,
+            None => return false,
+        };
+        let children = &parent.children;
+        let range = if i + 1 < children.len() {
+            children[i].start()..children[i + 1].start()
+        } else if i > 0 {
+            children[i - 1].range.end..children[i].range.end
+        } else {
+            parent.range.start + 1..parent.range.end - 1
+        };
+        self.splice(range, "").is_ok()
+    }
+
+    fn append(&mut self, container: &CstNode, source: &str) -> bool {
+        let last = match container.children.last() {
+            Some(last) => last,
+            None => {
+                let at = container.range.start + 1;
+                return self.splice(at..at, source).is_ok();
+            }
+        };
+        let start = last.start();
+        let line_start = self.text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &self.text[line_start..start];
+        let end = last.range.end;
+        if line_start <= container.range.start || !indent.chars().all(char::is_whitespace) {
+            return self.splice(end..end, &format!(", {}", source)).is_ok();
+        }
+
+        // One member per line: the new one goes on the next line, leaving a
+        // comment at the end of the last member's line where it was.
+        let newline = if self.text[..line_start].ends_with("\r\n") { "\r\n" } else { "\n" };
+        let tail = line_tail(&self.text[end..]);
+        let kept = &self.text[end..end + tail];
+        let comma = if kept.trim_start().starts_with(',') { "" } else { "," };
+        let replacement = format!("{}{}{}{}{}", comma, kept, newline, indent, source);
+        self.splice(end..end + tail, &replacement).is_ok()
+    }
+
+    fn splice(&mut self, range: Range<usize>, source: &str) -> Result<(), DecodeError> {
+        let mut text = self.text.clone();
+        text.replace_range(range, source);
+        self.root = parse_nodes(&text, &self.options)?;
+        self.text = text;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+fn parse_nodes(text: &str, options: &Options) -> Result<CstNode, DecodeError> {
+    let mut parser = Parser::new(text, options.clone());
+    let mut root = None;
+    let mut stack: Vec<CstNode> = Vec::new();
+    let mut key: Option<(String, Range<usize>)> = None;
+
+    while let Some(event) = parser.next_event()? {
+        let token = parser.token();
+        let kind = match event {
+            Event::Key(k) => {
+                key = Some((k.into_owned(), token));
+                continue;
+            }
+            Event::EndArray | Event::EndObject => {
+                let mut node = stack.pop().unwrap();
+                node.range.end = token.end;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => root = Some(node),
+                }
+                continue;
+            }
+            Event::StartArray => CstKind::Array,
+            Event::StartObject => CstKind::Object,
+            _ => CstKind::Scalar,
+        };
+        let (key, key_range) = match key.take() {
+            Some((k, range)) => (Some(k), Some(range)),
+            None => (None, None),
+        };
+        let node = CstNode {
+            kind,
+            range: token,
+            key_range,
+            key,
+            children: Vec::new(),
+        };
+        match (kind, stack.last_mut()) {
+            (CstKind::Scalar, Some(parent)) => parent.children.push(node),
+            (CstKind::Scalar, None) => root = Some(node),
+            _ => stack.push(node),
+        }
+    }
+
+    Ok(root.unwrap())
+}
+
+/// Length of the blanks, comma and comments that follow a value on its line.
+fn line_tail(text: &str) -> usize {
+    let mut i = text.len() - text.trim_start_matches([' ', '\t']).len();
+    if text[i..].starts_with(',') {
+        i += 1;
+    }
+    loop {
+        i = text.len() - text[i..].trim_start_matches([' ', '\t']).len();
+        let rest = &text[i..];
+        if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            return i + rest[..end].trim_end_matches('\r').len();
+        }
+        match rest.find("*/") {
+            Some(close) if rest.starts_with("/*") && !rest[..close].contains('\n') => {
+                i += close + 2
+            }
+            _ => return i,
+        }
+    }
+}
+
+/// Compact JSON for `value`, with strings escaped and non-finite floats
+/// written as `null`.
+fn to_source(value: &JFObject) -> String {
+    match *value {
+        JFObject::String(ref s) => quote(s),
+        JFObject::Integer(i) => i.to_string(),
+        JFObject::Float(f) if f.is_finite() => format!("{:?}", f),
+        JFObject::Float(_) | JFObject::Null => "null".to_owned(),
+        JFObject::Number(ref n) => n.as_str().to_owned(),
+        JFObject::Decimal(d) => d.to_string(),
+        JFObject::True => "true".to_owned(),
+        JFObject::False => "false".to_owned(),
+        JFObject::Array(ref v) => {
+            let items: Vec<String> = v.iter().map(to_source).collect();
+            format!("[{}]", items.join(", "))
+        }
+        JFObject::Dictionary(ref v) => {
+            let members: Vec<String> =
+                v.iter().map(|(k, v)| format!("{}: {}", quote(k), to_source(v))).collect();
+            format!("{{{}}}", members.join(", "))
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+
+use error::{DecodeError, ErrorKind};
+use json_flex::JFObject;
+use limits::Limit;
+use number::{Decimal, Number};
+use options::Options;
+use parser::{Event, Parser};
+
+/// One slot of the tape. Containers are followed by their contents, an
+/// object's as alternating keys and values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tape {
+    /// A byte range of `Document::strings`.
+    String(u32, u32),
+    Key(u32, u32),
+    Number(u32, u32),
+    Integer(i64),
+    Float(f64),
+    Decimal(Decimal),
+    /// The number of elements or members, and the index just past the
+    /// container's last descendant.
+    Array(u32, u32),
+    Object(u32, u32),
+    Null,
+    False,
+    True,
+}
+
+/// A decoded document held in two flat buffers, a tape of nodes and the text
+/// of every string, key and raw number, so that decoding allocates once per
+/// buffer rather than once per node. `load` reuses both buffers.
+///
+/// Object members stay in input order, duplicates included; `get` finds the
+/// last one. `Options::duplicate_keys` does not apply.
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    tape: Vec<Tape>,
+    strings: String,
+}
+
+/// A node of a `Document`.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeRef<'a> {
+    document: &'a Document,
+    index: u32,
+}
+
+impl Document {
+    pub fn parse(text: &str, options: &Options) -> Result<Document, DecodeError> {
+        let mut document = Document::default();
+        document.load(text, options)?;
+        Ok(document)
+    }
+
+    /// Replaces the contents with `text` decoded, keeping the buffers'
+    /// capacity. The document is empty if decoding fails.
+    pub fn load(&mut self, text: &str, options: &Options) -> Result<(), DecodeError> {
+        self.tape.clear();
+        self.strings.clear();
+        // Every node and every byte of string text comes from at least one
+        // byte of input, so this keeps all indices within `u32`.
+        if text.len() > u32::MAX as usize {
+            return Err(DecodeError::new(ErrorKind::LimitExceeded(Limit::InputBytes), text, 0));
+        }
+        let result = self.fill(&mut Parser::new(text, options.clone()));
+        if result.is_err() {
+            self.tape.clear();
+            self.strings.clear();
+        }
+        result
+    }
+
+    fn fill(&mut self, parser: &mut Parser) -> Result<(), DecodeError> {
+        // Open containers, with the number of children seen so far.
+        let mut stack: Vec<(usize, u32)> = Vec::new();
+        while let Some(event) = parser.next_event()? {
+            let slot = match event {
+                Event::EndArray | Event::EndObject => {
+                    let (start, len) = stack.pop().unwrap();
+                    let end = self.tape.len() as u32;
+                    self.tape[start] = match self.tape[start] {
+                        Tape::Array(..) => Tape::Array(len, end),
+                        _ => Tape::Object(len, end),
+                    };
+                    continue;
+                }
+                Event::Key(k) => {
+                    let key = self.push_str(&k);
+                    self.tape.push(Tape::Key(key.0, key.1));
+                    continue;
+                }
+                Event::StartArray => Tape::Array(0, 0),
+                Event::StartObject => Tape::Object(0, 0),
+                Event::String(s) => {
+                    let (start, end) = self.push_str(&s);
+                    Tape::String(start, end)
+                }
+                Event::Number(n) => {
+                    let (start, end) = self.push_str(&n);
+                    Tape::Number(start, end)
+                }
+                Event::Integer(i) => Tape::Integer(i),
+                Event::Float(f) => Tape::Float(f),
+                Event::Decimal(d) => Tape::Decimal(d),
+                Event::Bool(true) => Tape::True,
+                Event::Bool(false) => Tape::False,
+                Event::Null => Tape::Null,
+            };
+            if let Some(open) = stack.last_mut() {
+                open.1 += 1;
+            }
+            if let Tape::Array(..) | Tape::Object(..) = slot {
+                stack.push((self.tape.len(), 0));
+            }
+            self.tape.push(slot);
+        }
+        if self.tape.is_empty() {
+            self.tape.push(Tape::Null);
+        }
+        Ok(())
+    }
+
+    fn push_str(&mut self, s: &str) -> (u32, u32) {
+        let start = self.strings.len() as u32;
+        self.strings.push_str(s);
+        (start, self.strings.len() as u32)
+    }
+
+    pub fn root(&self) -> NodeRef<'_> {
+        NodeRef {
+            document: self,
+            index: 0,
+        }
+    }
+
+    /// Nodes in the document, object keys included.
+    pub fn len(&self) -> usize {
+        self.tape.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tape.is_empty()
+    }
+
+    pub fn to_jfobject(&self) -> JFObject {
+        self.root().to_jfobject()
+    }
+}
+
+impl<'a> NodeRef<'a> {
+    /// `Null` for the root of an empty document.
+    fn tape(&self) -> Tape {
+        self.document.tape.get(self.index as usize).cloned().unwrap_or(Tape::Null)
+    }
+
+    fn at_index(&self, index: u32) -> NodeRef<'a> {
+        NodeRef {
+            document: self.document,
+            index,
+        }
+    }
+
+    fn text(&self, start: u32, end: u32) -> &'a str {
+        &self.document.strings[start as usize..end as usize]
+    }
+
+    /// Index of the node after this one and its descendants.
+    fn skip(&self) -> u32 {
+        match self.tape() {
+            Tape::Array(_, end) | Tape::Object(_, end) => end,
+            _ => self.index + 1,
+        }
+    }
+
+    pub fn into_string(&self) -> Option<&'a str> {
+        match self.tape() {
+            Tape::String(start, end) => Some(self.text(start, end)),
+            _ => None,
+        }
+    }
+    pub fn into_i64(&self) -> Option<i64> {
+        match self.tape() {
+            Tape::Integer(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_f64(&self) -> Option<f64> {
+        match self.tape() {
+            Tape::Float(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_number(&self) -> Option<Number> {
+        match self.tape() {
+            Tape::Number(start, end) => Some(Number::from_lexeme(self.text(start, end))),
+            _ => None,
+        }
+    }
+    pub fn into_decimal(&self) -> Option<Decimal> {
+        match self.tape() {
+            Tape::Decimal(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        self.tape() == Tape::Null
+    }
+    pub fn is_true(&self) -> bool {
+        self.tape() == Tape::True
+    }
+    pub fn is_false(&self) -> bool {
+        self.tape() == Tape::False
+    }
+    pub fn is_array(&self) -> bool {
+        matches!(self.tape(), Tape::Array(..))
+    }
+    pub fn is_dictionary(&self) -> bool {
+        matches!(self.tape(), Tape::Object(..))
+    }
+    pub fn is_string(&self) -> bool {
+        matches!(self.tape(), Tape::String(..))
+    }
+    pub fn is_integer(&self) -> bool {
+        matches!(self.tape(), Tape::Integer(_))
+    }
+    pub fn is_float(&self) -> bool {
+        matches!(self.tape(), Tape::Float(_))
+    }
+    pub fn is_number(&self) -> bool {
+        matches!(self.tape(), Tape::Number(..))
+    }
+    pub fn is_decimal(&self) -> bool {
+        matches!(self.tape(), Tape::Decimal(_))
+    }
+    pub fn unwrap_string(&self) -> &'a str {
+        self.into_string().unwrap()
+    }
+    pub fn unwrap_i64(&self) -> i64 {
+        self.into_i64().unwrap()
+    }
+    pub fn unwrap_f64(&self) -> f64 {
+        self.into_f64().unwrap()
+    }
+    pub fn unwrap_number(&self) -> Number {
+        self.into_number().unwrap()
+    }
+    pub fn unwrap_decimal(&self) -> Decimal {
+        self.into_decimal().unwrap()
+    }
+
+    /// Elements of an array or members of an object; 0 for anything else.
+    pub fn len(&self) -> usize {
+        match self.tape() {
+            Tape::Array(len, _) | Tape::Object(len, _) => len as usize,
+            _ => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The member for `key`; the last one if the key is repeated.
+    pub fn get(&self, key: &str) -> Option<NodeRef<'a>> {
+        self.members().filter(|m| m.0 == key).last().map(|m| m.1)
+    }
+
+    /// The array element at `index`.
+    pub fn at(&self, index: usize) -> Option<NodeRef<'a>> {
+        self.elements().nth(index)
+    }
+
+    /// Elements of an array; nothing for anything else.
+    pub fn elements(&self) -> Elements<'a> {
+        let (next, end) = match self.tape() {
+            Tape::Array(_, end) => (self.index + 1, end),
+            _ => (0, 0),
+        };
+        Elements {
+            node: self.at_index(next),
+            end,
+        }
+    }
+
+    /// Keys and values of an object in input order; nothing for anything else.
+    pub fn members(&self) -> Members<'a> {
+        let (next, end) = match self.tape() {
+            Tape::Object(_, end) => (self.index + 1, end),
+            _ => (0, 0),
+        };
+        Members {
+            node: self.at_index(next),
+            end,
+        }
+    }
+
+    pub fn to_jfobject(&self) -> JFObject {
+        match self.tape() {
+            Tape::String(start, end) => JFObject::String(self.text(start, end).to_owned()),
+            Tape::Number(start, end) => {
+                JFObject::Number(Number::from_lexeme(self.text(start, end)))
+            }
+            Tape::Integer(i) => JFObject::Integer(i),
+            Tape::Float(f) => JFObject::Float(f),
+            Tape::Decimal(d) => JFObject::Decimal(d),
+            Tape::Array(len, _) => {
+                let mut v = Vec::with_capacity(len as usize);
+                v.extend(self.elements().map(|e| e.to_jfobject()));
+                JFObject::Array(v)
+            }
+            Tape::Object(len, _) => {
+                let mut map = HashMap::with_capacity(len as usize);
+                for (k, v) in self.members() {
+                    map.insert(k.to_owned(), v.to_jfobject());
+                }
+                JFObject::Dictionary(map)
+            }
+            Tape::Null | Tape::Key(..) => JFObject::Null,
+            Tape::False => JFObject::False,
+            Tape::True => JFObject::True,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_jfobject().to_json()
+    }
+}
+
+pub struct Elements<'a> {
+    node: NodeRef<'a>,
+    end: u32,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<NodeRef<'a>> {
+        if self.node.index >= self.end {
+            return None;
+        }
+        let node = self.node;
+        self.node.index = node.skip();
+        Some(node)
+    }
+}
+
+pub struct Members<'a> {
+    node: NodeRef<'a>,
+    end: u32,
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = (&'a str, NodeRef<'a>);
+
+    fn next(&mut self) -> Option<(&'a str, NodeRef<'a>)> {
+        if self.node.index >= self.end {
+            return None;
+        }
+        let key = match self.node.tape() {
+            Tape::Key(start, end) => self.node.text(start, end),
+            _ => unreachable!(),
+        };
+        let value = self.node.at_index(self.node.index + 1);
+        self.node.index = value.skip();
+        Some((key, value))
+    }
+}
@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use limits::Limit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset into the input.
+    pub offset: usize,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+impl Position {
+    pub fn from_offset(text: &str, offset: usize) -> Position {
+        let mut line = 1;
+        let mut column = 1;
+        for (i, c) in text.char_indices() {
+            if i >= offset {
+                break;
+            }
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        Position {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    UnexpectedEndOfInput,
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidNumber,
+    InvalidEscape,
+    InvalidUtf8,
+    TrailingCharacters,
+    /// Rejected by `DuplicateKeys::Error`.
+    DuplicateKey(String),
+    /// A bound from `Options::limits` was exceeded.
+    LimitExceeded(Limit),
+    /// Reading the input failed.
+    Io(io::ErrorKind),
+    /// Refused by a `Visitor`, with its reason.
+    Rejected(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub kind: ErrorKind,
+    pub position: Position,
+    /// What would have been accepted at `position`, such as `"','"` or
+    /// `"value"`; empty when the error is inside a token.
+    pub expected: Vec<&'static str>,
+    /// A guess at the cause, such as a missing comma.
+    pub hint: Option<&'static str>,
+    /// The line containing the error, shortened to about 80 characters around
+    /// it.
+    pub source_line: String,
+    /// Character index of the error within `source_line`.
+    pub caret: usize,
+}
+
+/// Characters of context kept on each side of the error in `source_line`.
+pub(crate) const CONTEXT: usize = 40;
+
+impl DecodeError {
+    pub fn new(kind: ErrorKind, text: &str, offset: usize) -> DecodeError {
+        DecodeError::at(kind, text, Position::from_offset(text, offset))
+    }
+
+    /// The error at `position`, already located in `text`. Only the context
+    /// kept in `source_line` is read, however long the line.
+    pub(crate) fn at(kind: ErrorKind, text: &str, position: Position) -> DecodeError {
+        let offset = position.offset;
+        let mut before: Vec<char> = text[..offset].chars()
+            .rev()
+            .take_while(|&c| c != '\n')
+            .take(CONTEXT + 1)
+            .collect();
+        before.reverse();
+        let mut source_line = String::new();
+        if before.len() > CONTEXT {
+            source_line.push_str("...");
+        }
+        source_line.extend(&before[before.len().saturating_sub(CONTEXT)..]);
+        let caret = source_line.chars().count();
+        let mut after = text[offset..].chars().take_while(|&c| c != '\n' && c != '\r');
+        source_line.extend(after.by_ref().take(CONTEXT));
+        if after.next().is_some() {
+            source_line.push_str("...");
+        }
+
+        DecodeError {
+            kind,
+            position,
+            expected: Vec::new(),
+            hint: None,
+            source_line,
+            caret,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+            ErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c),
+            ErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            ErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            ErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            ErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            ErrorKind::TrailingCharacters => write!(f, "trailing characters after value"),
+            ErrorKind::DuplicateKey(ref k) => write!(f, "duplicate key {:?}", k),
+            ErrorKind::LimitExceeded(limit) => write!(f, "{} limit exceeded", limit),
+            ErrorKind::Io(kind) => write!(f, "read error: {}", kind),
+            ErrorKind::Rejected(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// The message, then the source line with a caret under the error, the
+/// expected tokens and the hint:
+///
+/// ```text
+/// unexpected character '"' at line 2 column 5
+///   |
+/// 2 |     "b": 2
+///   |     ^
+///   = expected ',' or '}'
+///   = hint: missing comma between object members
+/// ```
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{} at line {} column {}",
+               self.kind,
+               self.position.line,
+               self.position.column)?;
+
+        let line = self.position.line.to_string();
+        let gutter = " ".repeat(line.len());
+        let indent: String = self.source_line
+            .chars()
+            .take(self.caret)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        write!(f, "\n{} |\n{} | {}\n{} | {}^", gutter, line, self.source_line, gutter, indent)?;
+
+        if let Some((last, rest)) = self.expected.split_last() {
+            write!(f, "\n{} = expected ", gutter)?;
+            if !rest.is_empty() {
+                write!(f, "{} or ", rest.join(", "))?;
+            }
+            write!(f, "{}", last)?;
+        }
+        if let Some(hint) = self.hint {
+            write!(f, "\n{} = hint: {}", gutter, hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for DecodeError {}
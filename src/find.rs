@@ -0,0 +1,106 @@
+use std::ops::Range;
+
+use json_flex::JFObject;
+use options::Options;
+use parser::{Event, Parser};
+use visitor::build;
+
+/// A JSON value found inside other text.
+#[derive(Debug, Clone)]
+pub struct Found {
+    /// Byte range of the value in the searched text.
+    pub range: Range<usize>,
+    pub value: JFObject,
+}
+
+/// Every object or array in `text` that decodes on its own, such as the
+/// payload of a log line or of a ```` ```json ```` block in markdown. Values
+/// nested in a found value are not reported separately.
+pub fn find_json(text: &str) -> Vec<Found> {
+    find_json_with(text, &Options::default())
+}
+
+pub fn find_json_with(text: &str, options: &Options) -> Vec<Found> {
+    let mut found = Vec::new();
+    search(text, 0..text.len(), options, &mut found);
+    found
+}
+
+/// Tries every `{` and `[` in `range`. After a failed attempt the search
+/// goes on from where it failed, so that no text is parsed more than a few
+/// times; of the text it passed over, only the objects and arrays that
+/// were complete in it are tried again.
+fn search(text: &str, range: Range<usize>, options: &Options, found: &mut Vec<Found>) {
+    let mut pos = range.start;
+    while let Some(i) = text[pos..range.end].find(['{', '[']) {
+        let start = pos + i;
+        match decode_prefix(&text[start..range.end], options) {
+            Ok((value, len)) => {
+                found.push(Found {
+                    range: start..start + len,
+                    value,
+                });
+                pos = start + len;
+            }
+            Err((failed_at, complete)) => {
+                for inner in complete {
+                    search(text, start + inner.start..start + inner.end, options, found);
+                }
+                pos = start + failed_at.max(1);
+            }
+        }
+    }
+}
+
+/// Where an attempt failed, and the outermost objects and arrays completed
+/// inside it before that.
+type Failure = (usize, Vec<Range<usize>>);
+
+/// Decodes the value at the start of `text`, returning it with its length.
+fn decode_prefix(text: &str, options: &Options) -> Result<(JFObject, usize), Failure> {
+    let mut parser = Parser::new(text, options.clone());
+    parser.allow_trailing();
+    let mut open = Vec::new();
+    let mut complete: Vec<Range<usize>> = Vec::new();
+    let result = build(&mut parser, |parser, event| {
+        match event {
+            Some(&Event::StartArray) | Some(&Event::StartObject) => open.push(parser.token().start),
+            Some(&Event::EndArray) | Some(&Event::EndObject) => {
+                let start = open.pop().unwrap();
+                if !open.is_empty() {
+                    while complete.last().is_some_and(|r| r.start > start) {
+                        complete.pop();
+                    }
+                    complete.push(start..parser.token().end);
+                }
+            }
+            _ => {}
+        }
+    });
+    match result {
+        Ok(value) => Ok((value, parser.offset())),
+        Err(e) => Err((e.position.offset, complete)),
+    }
+}
+
+/// Splits a JSONP response such as `callback({...});` into the callback name
+/// and the decoded argument.
+pub fn unwrap_jsonp(text: &str) -> Option<(String, JFObject)> {
+    let open = text.find('(')?;
+    let close = text.rfind(')')?;
+    if close < open || !text[close + 1..].chars().all(|c| c.is_whitespace() || c == ';') {
+        return None;
+    }
+    let prefix = text[..open].trim_end();
+    let name_start = prefix.char_indices()
+        .rev()
+        .find(|&(_, c)| !(c.is_alphanumeric() || "_$.".contains(c)))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let callback = &prefix[name_start..];
+    if callback.is_empty() {
+        return None;
+    }
+    let mut parser = Parser::new(&text[open + 1..close], Options::javascript());
+    let value = build(&mut parser, |_, _| {}).ok()?;
+    Some((callback.to_owned(), value))
+}
@@ -0,0 +1,348 @@
+use std::str;
+
+use error::{DecodeError, ErrorKind, Position};
+use json_flex::JFObject;
+use limits::Limit;
+use options::Options;
+use parser::{Event, Parser};
+use visitor::{decode_into, JFObjectBuilder};
+
+/// Where the scan of the buffered input stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scan {
+    Normal,
+    /// Inside a string with the given quote.
+    String(u8),
+    /// After a backslash in a string.
+    Escape(u8),
+    /// After a `/` that may start a comment.
+    Slash,
+    LineComment,
+    /// Inside a block comment, after a `*` or not.
+    BlockComment(bool),
+}
+
+/// Decodes a stream of top-level values fed in chunks of any size, such as
+/// reads from a socket, and hands back each value as soon as its last byte
+/// arrives. Values may be separated by whitespace, or comments if enabled.
+///
+/// Only the value being received is buffered. A chunk may end anywhere, in
+/// a string, an escape, a number or a UTF-8 sequence; a top-level number or
+/// literal is complete once something follows it, or at `finish`. With
+/// `Options::bareword_values` a top-level bareword such as `x y` runs to the
+/// end of the line, as it does for `decode_with`. Error
+/// positions count from the start of the stream, and decoding carries on
+/// with the next value, except after a value longer than
+/// `max_input_bytes`, which ends the stream.
+pub struct IncrementalParser {
+    options: Options,
+    buffer: Vec<u8>,
+    /// Where the scan resumes in `buffer`.
+    scanned: usize,
+    scan: Scan,
+    /// Closing brackets of the open containers.
+    open: Vec<u8>,
+    /// Whether the innermost container is an object expecting a key.
+    key: bool,
+    /// Whether the scan is in a bareword, which runs to the next `,`,
+    /// bracket, line break or comment.
+    word: bool,
+    /// Start of the value being received, and whether it is a bare scalar.
+    value: Option<(usize, bool)>,
+    /// Start of the comment being skipped.
+    comment: usize,
+    /// Position of `buffer[0]` in the stream.
+    base: Position,
+    /// Set once a value has exceeded `max_input_bytes`.
+    exceeded: bool,
+}
+
+impl IncrementalParser {
+    pub fn new(options: &Options) -> IncrementalParser {
+        IncrementalParser {
+            options: options.clone(),
+            buffer: Vec::new(),
+            scanned: 0,
+            scan: Scan::Normal,
+            open: Vec::new(),
+            key: false,
+            word: false,
+            value: None,
+            comment: 0,
+            base: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            exceeded: false,
+        }
+    }
+
+    /// Adds `bytes` to the input and returns the values they complete, in
+    /// order.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Result<JFObject, DecodeError>> {
+        if self.exceeded {
+            return Vec::new();
+        }
+        self.buffer.extend_from_slice(bytes);
+        let mut values = Vec::new();
+        while let Some(range) = self.next_value() {
+            values.push(self.decode(range.0, range.1));
+        }
+
+        if let Some((start, _)) = self.value {
+            let max = self.options.limits.max_input_bytes;
+            if max.is_some_and(|max| self.buffer.len() - start > max) {
+                let error = DecodeError::new(ErrorKind::LimitExceeded(Limit::InputBytes), "", 0);
+                values.push(Err(self.rebase(error, start)));
+                self.exceeded = true;
+                self.buffer = Vec::new();
+                return values;
+            }
+        }
+
+        let keep = match (self.value, self.scan) {
+            (Some((start, _)), _) => start,
+            (None, Scan::Slash) => self.scanned - 1,
+            (None, Scan::LineComment) | (None, Scan::BlockComment(_)) => self.comment,
+            (None, _) => self.scanned,
+        };
+        self.base = advance(self.base, &self.buffer[..keep]);
+        self.buffer.drain(..keep);
+        self.scanned -= keep;
+        self.comment = self.comment.saturating_sub(keep);
+        if let Some((ref mut start, _)) = self.value {
+            *start -= keep;
+        }
+        values
+    }
+
+    /// Ends the input, returning the value still being received, if any,
+    /// or the error for it being cut short.
+    pub fn finish(self) -> Option<Result<JFObject, DecodeError>> {
+        let start = match (self.value, self.scan) {
+            _ if self.exceeded => return None,
+            (Some((start, _)), _) => start,
+            (None, Scan::Slash) => self.scanned - 1,
+            (None, Scan::BlockComment(_)) => self.comment,
+            (None, _) => return None,
+        };
+        Some(self.decode(start, self.buffer.len()))
+    }
+
+    /// Scans on from `scanned` to the end of the next complete value.
+    fn next_value(&mut self) -> Option<(usize, usize)> {
+        let barewords = self.options.bareword_values;
+        while self.scanned < self.buffer.len() {
+            let i = self.scanned;
+            let b = self.buffer[i];
+            if let Some((start, true)) = self.value {
+                let mut ends = if barewords { self.ends_word(i)? } else { self.is_delimiter(b) };
+                if barewords && !self.word && is_space(b) {
+                    ends = self.is_number(start, i);
+                    self.word = !ends;
+                }
+                if ends {
+                    self.value = None;
+                    self.word = false;
+                    return Some((start, i));
+                }
+                if barewords {
+                    // Part of the scalar, whatever the byte: a top-level
+                    // number turns out to be a bareword if anything but a
+                    // space follows it.
+                    self.scanned += 1;
+                    continue;
+                }
+            }
+            if self.word {
+                if !self.ends_word(i)? {
+                    self.scanned += 1;
+                    continue;
+                }
+                self.word = false;
+            }
+            self.scanned += 1;
+            match self.scan {
+                Scan::String(quote) if b == quote => {
+                    self.scan = Scan::Normal;
+                    if self.open.is_empty() {
+                        return self.value.take().map(|(start, _)| (start, i + 1));
+                    }
+                }
+                Scan::String(quote) if b == b'\\' => self.scan = Scan::Escape(quote),
+                Scan::Escape(quote) => self.scan = Scan::String(quote),
+                Scan::String(_) => {}
+                Scan::Slash => {
+                    self.scan = match b {
+                        b'/' => Scan::LineComment,
+                        b'*' => Scan::BlockComment(false),
+                        _ => {
+                            // Not a comment: the `/` is part of a value, for
+                            // the decoder to reject.
+                            self.scalar(i - 1, b'/');
+                            self.scanned -= 1;
+                            Scan::Normal
+                        }
+                    };
+                    if self.scan != Scan::Normal && self.value.is_none() {
+                        self.comment = i - 1;
+                    }
+                }
+                Scan::LineComment => {
+                    if b == b'\n' || b == b'\r' {
+                        self.scan = Scan::Normal;
+                    }
+                }
+                Scan::BlockComment(star) => {
+                    self.scan = match b {
+                        b'/' if star => Scan::Normal,
+                        b'*' => Scan::BlockComment(true),
+                        _ => Scan::BlockComment(false),
+                    };
+                }
+                Scan::Normal => {
+                    match b {
+                        b'"' => {
+                            self.start(i, false);
+                            self.scan = Scan::String(b);
+                        }
+                        b'\'' if self.options.single_quotes => {
+                            self.start(i, false);
+                            self.scan = Scan::String(b);
+                        }
+                        b'/' if self.options.comments => self.scan = Scan::Slash,
+                        b'{' | b'[' => {
+                            self.start(i, false);
+                            self.open.push(if b == b'{' { b'}' } else { b']' });
+                            self.key = b == b'{';
+                        }
+                        b'(' if self.options.python_literals => {
+                            self.start(i, false);
+                            self.open.push(b')');
+                            self.key = false;
+                        }
+                        b'}' | b']' | b')' if !self.open.is_empty() &&
+                                              (b != b')' || self.options.python_literals) => {
+                            self.open.pop();
+                            self.key = false;
+                            if self.open.is_empty() {
+                                return self.value.take().map(|(start, _)| (start, i + 1));
+                            }
+                        }
+                        b',' => self.key = self.open.last() == Some(&b'}'),
+                        b':' => self.key = false,
+                        b' ' | b'\t' | b'\n' | b'\r' => {}
+                        _ => self.scalar(i, b),
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn start(&mut self, at: usize, scalar: bool) {
+        if self.value.is_none() {
+            self.value = Some((at, scalar && self.open.is_empty()));
+        }
+    }
+
+    /// Starts a number, literal or bareword at `at`, beginning with `b`.
+    fn scalar(&mut self, at: usize, b: u8) {
+        self.start(at, true);
+        let number = b == b'-' || b.is_ascii_digit() ||
+                     (self.options.json5 && (b == b'+' || b == b'.'));
+        // A top-level number is left to `is_number`.
+        self.word = self.options.bareword_values && !self.key &&
+                    !(number && self.open.is_empty());
+    }
+
+    /// Whether `b` ends a top-level number or literal.
+    fn is_delimiter(&self, b: u8) -> bool {
+        match b {
+            b'(' | b')' => self.options.python_literals,
+            _ => {
+                matches!(b,
+                         b' ' | b'\t' | b'\n' | b'\r' | b'{' | b'}' | b'[' | b']' | b',' |
+                         b':' | b'"' | b'\'' | b'/')
+            }
+        }
+    }
+
+    /// Whether the byte at `i` ends a bareword, or `None` if that depends on
+    /// the byte after it, which has yet to arrive.
+    fn ends_word(&self, i: usize) -> Option<bool> {
+        Some(match self.buffer[i] {
+            b',' | b'[' | b']' | b'{' | b'}' | b'\n' | b'\r' => true,
+            b')' => self.open.last() == Some(&b')'),
+            b'/' if self.options.comments => {
+                matches!(*self.buffer.get(i + 1)?, b'/' | b'*')
+            }
+            _ => false,
+        })
+    }
+
+    /// Whether the top-level scalar in `start..end` decodes to something
+    /// other than a bareword, and so ends at the space after it.
+    fn is_number(&self, start: usize, end: usize) -> bool {
+        let text = match str::from_utf8(&self.buffer[start..end]) {
+            Ok(text) => text,
+            Err(_) => return true,
+        };
+        let mut parser = Parser::new(text, self.options.clone());
+        parser.allow_trailing();
+        !matches!(parser.next_event(), Ok(Some(Event::String(_))))
+    }
+
+    fn decode(&self, start: usize, end: usize) -> Result<JFObject, DecodeError> {
+        let bytes = &self.buffer[start..end];
+        let text = match str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => {
+                let text = String::from_utf8_lossy(bytes);
+                let error = DecodeError::new(ErrorKind::InvalidUtf8, &text, e.valid_up_to());
+                return Err(self.rebase(error, start));
+            }
+        };
+        decode_into(text, &self.options, JFObjectBuilder::new(&self.options))
+            .map_err(|e| self.rebase(e, start))
+    }
+
+    /// `error`, found in a value starting at `start`, with its position in
+    /// the stream.
+    fn rebase(&self, mut error: DecodeError, start: usize) -> DecodeError {
+        let at = advance(self.base, &self.buffer[..start]);
+        let p = &mut error.position;
+        if p.line == 1 {
+            p.column += at.column - 1;
+        }
+        p.line += at.line - 1;
+        p.offset += at.offset;
+        error
+    }
+}
+
+impl Default for IncrementalParser {
+    fn default() -> IncrementalParser {
+        IncrementalParser::new(&Options::default())
+    }
+}
+
+/// The position after `bytes`, which start at `from` and end on a character
+/// boundary.
+fn advance(mut from: Position, bytes: &[u8]) -> Position {
+    for &b in bytes {
+        if b == b'\n' {
+            from.line += 1;
+            from.column = 1;
+        } else if b & 0xC0 != 0x80 {
+            from.column += 1;
+        }
+    }
+    from.offset += bytes.len();
+    from
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t')
+}
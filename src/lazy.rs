@@ -0,0 +1,339 @@
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use error::{DecodeError, ErrorKind};
+use json_flex::JFObject;
+use options::{ArrayHoles, DuplicateKeys, Options};
+use parser::Parser;
+use path::PathSegment;
+use structural::structural_index;
+use visitor::build;
+
+/// Input indexed for on-demand decoding. `new` finds the brackets, commas
+/// and colons in one pass and pairs the brackets; after that only the values
+/// that are navigated to or decoded are parsed, and everything else is
+/// skipped by jumping to the matching bracket. Syntax errors in the parts
+/// that are skipped go unnoticed.
+pub struct Lazy<'a> {
+    text: &'a str,
+    options: Options,
+    positions: Vec<u32>,
+    /// For each opening bracket in `positions`, the index of its partner.
+    partners: Vec<u32>,
+    /// The elements of every array `at` has looked into, by the index of its
+    /// opening bracket.
+    elements: Mutex<HashMap<usize, Vec<Slot>>>,
+}
+
+/// The `start`, `end` and `entry` of a `LazyValue`.
+type Slot = (usize, usize, usize);
+
+/// A value in a `Lazy` input, not yet decoded.
+#[derive(Clone, Copy)]
+pub struct LazyValue<'b, 'a: 'b> {
+    lazy: &'b Lazy<'a>,
+    /// The text between the separators around the value, including any
+    /// whitespace and comments.
+    start: usize,
+    end: usize,
+    /// Index in `positions` of the first separator or bracket from `start`.
+    entry: usize,
+}
+
+impl<'a> Lazy<'a> {
+    pub fn new(text: &'a str, options: &Options) -> Result<Lazy<'a>, DecodeError> {
+        let positions = structural_index(text, options)?;
+        let bytes = text.as_bytes();
+        let mut partners = vec![0; positions.len()];
+        let mut open: Vec<usize> = Vec::new();
+        for (i, &p) in positions.iter().enumerate() {
+            let b = bytes[p as usize];
+            match b {
+                b'{' | b'[' | b'(' => open.push(i),
+                b'}' | b']' | b')' => {
+                    match open.pop() {
+                        Some(o) if partner(bytes[positions[o] as usize]) == b => {
+                            partners[o] = i as u32
+                        }
+                        _ => {
+                            let kind = ErrorKind::UnexpectedCharacter(b as char);
+                            return Err(DecodeError::new(kind, text, p as usize));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !open.is_empty() {
+            return Err(DecodeError::new(ErrorKind::UnexpectedEndOfInput, text, text.len()));
+        }
+        Ok(Lazy {
+            text,
+            options: options.clone(),
+            positions,
+            partners,
+            elements: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn root<'b>(&'b self) -> LazyValue<'b, 'a> {
+        LazyValue {
+            lazy: self,
+            start: 0,
+            end: self.text.len(),
+            entry: 0,
+        }
+    }
+
+    fn byte(&self, entry: usize) -> u8 {
+        self.text.as_bytes()[self.positions[entry] as usize]
+    }
+}
+
+impl<'b, 'a: 'b> LazyValue<'b, 'a> {
+    fn parser(&self) -> Parser<'a> {
+        Parser::new(self.lazy.text, self.lazy.options.clone())
+    }
+
+    /// The offset and first byte of the value; `None` for an empty slot.
+    fn token(&self) -> Result<Option<(usize, u8)>, DecodeError> {
+        let mut parser = self.parser();
+        Ok(match parser.seek(self.start)? {
+            Some(b) if parser.offset() < self.end => Some((parser.offset(), b)),
+            _ => None,
+        })
+    }
+
+    /// The indices of the brackets around an array or object.
+    fn brackets(&self) -> Result<Option<(usize, usize)>, DecodeError> {
+        let offset = match self.token()? {
+            Some((offset, b'{')) | Some((offset, b'[')) | Some((offset, b'(')) => offset,
+            _ => return Ok(None),
+        };
+        Ok(match self.lazy.positions.get(self.entry) {
+            Some(&p) if p as usize == offset => {
+                Some((self.entry, self.lazy.partners[self.entry] as usize))
+            }
+            _ => None,
+        })
+    }
+
+    pub fn is_array(&self) -> Result<bool, DecodeError> {
+        Ok(self.brackets()?.is_some_and(|(open, _)| self.lazy.byte(open) != b'{'))
+    }
+
+    pub fn is_dictionary(&self) -> Result<bool, DecodeError> {
+        Ok(self.brackets()?.is_some_and(|(open, _)| self.lazy.byte(open) == b'{'))
+    }
+
+    /// The comma-separated slots between a pair of brackets.
+    fn slots(&self, open: usize, close: usize) -> Vec<LazyValue<'b, 'a>> {
+        let lazy = self.lazy;
+        let mut slots = Vec::new();
+        let mut slot = LazyValue {
+            lazy,
+            start: lazy.positions[open] as usize + 1,
+            end: 0,
+            entry: open + 1,
+        };
+        let mut i = open + 1;
+        while i < close {
+            match lazy.byte(i) {
+                b'{' | b'[' | b'(' => i = lazy.partners[i] as usize,
+                b',' => {
+                    slot.end = lazy.positions[i] as usize;
+                    slots.push(slot);
+                    slot.start = slot.end + 1;
+                    slot.entry = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        slot.end = lazy.positions[close] as usize;
+        slots.push(slot);
+        slots
+    }
+
+    /// The slots that hold values, after trailing commas and holes have been
+    /// dealt with as `decode_with` would.
+    fn filled(&self, open: usize, close: usize) -> Result<Vec<LazyValue<'b, 'a>>, DecodeError> {
+        let object = self.lazy.byte(open) == b'{';
+        let slots = self.slots(open, close);
+        let count = slots.len();
+        let mut filled = Vec::with_capacity(count);
+        for (i, slot) in slots.into_iter().enumerate() {
+            if slot.token()?.is_some() {
+                filled.push(slot);
+                continue;
+            }
+            if count == 1 || (i + 1 == count && self.lazy.options.trailing_commas) {
+                break;
+            }
+            match self.lazy.options.array_holes {
+                ArrayHoles::Null if !object => filled.push(slot),
+                ArrayHoles::Skip if !object => {}
+                _ => {
+                    let c = self.lazy.text[slot.end..].chars().next().unwrap();
+                    let kind = ErrorKind::UnexpectedCharacter(c);
+                    return Err(DecodeError::new(kind, self.lazy.text, slot.end));
+                }
+            }
+        }
+        Ok(filled)
+    }
+
+    /// The elements of an array; empty for anything else.
+    pub fn elements(&self) -> Result<Vec<LazyValue<'b, 'a>>, DecodeError> {
+        match self.brackets()? {
+            Some((open, close)) if self.lazy.byte(open) != b'{' => self.filled(open, close),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// The keys and values of an object in input order; empty for anything
+    /// else. Only the keys are decoded.
+    pub fn members(&self) -> Result<Vec<(Cow<'a, str>, LazyValue<'b, 'a>)>, DecodeError> {
+        let (open, close) = match self.brackets()? {
+            Some((open, close)) if self.lazy.byte(open) == b'{' => (open, close),
+            _ => return Ok(Vec::new()),
+        };
+        let mut members = Vec::new();
+        for slot in self.filled(open, close)? {
+            members.push(slot.member()?);
+        }
+        Ok(members)
+    }
+
+    /// Splits an object slot into its key and value.
+    fn member(self) -> Result<(Cow<'a, str>, LazyValue<'b, 'a>), DecodeError> {
+        let lazy = self.lazy;
+        let mut parser = self.parser();
+        parser.seek(self.start)?;
+        let key = parser.read_key()?;
+        let found = parser.seek(parser.offset())?;
+        let colon = parser.offset();
+        match lazy.positions.binary_search(&(colon as u32)) {
+            Ok(i) if found == Some(b':') && colon < self.end => {
+                let value = LazyValue {
+                    lazy,
+                    start: colon + 1,
+                    end: self.end,
+                    entry: i + 1,
+                };
+                Ok((key, value))
+            }
+            _ => {
+                let kind = match lazy.text[colon..].chars().next() {
+                    Some(c) => ErrorKind::UnexpectedCharacter(c),
+                    None => ErrorKind::UnexpectedEndOfInput,
+                };
+                Err(DecodeError::new(kind, lazy.text, colon))
+            }
+        }
+    }
+
+    /// The member for `key`, following `Options::duplicate_keys` when it is
+    /// repeated: the first or the last value, or an `ErrorKind::DuplicateKey`
+    /// error at the second key. `DuplicateKeys::Collect` gives the last value,
+    /// and `members` every one. Keys are decoded up to the match, values not
+    /// at all.
+    pub fn get(&self, key: &str) -> Result<Option<LazyValue<'b, 'a>>, DecodeError> {
+        let (open, close) = match self.brackets()? {
+            Some((open, close)) if self.lazy.byte(open) == b'{' => (open, close),
+            _ => return Ok(None),
+        };
+        let duplicate_keys = self.lazy.options.duplicate_keys;
+        let mut found = None;
+        for slot in self.filled(open, close)? {
+            let (k, value) = slot.member()?;
+            if k != key {
+                continue;
+            }
+            if found.is_some() && duplicate_keys == DuplicateKeys::Error {
+                let at = slot.token()?.map_or(slot.start, |(offset, _)| offset);
+                let kind = ErrorKind::DuplicateKey(key.to_owned());
+                return Err(DecodeError::new(kind, self.lazy.text, at));
+            }
+            found = Some(value);
+            if duplicate_keys == DuplicateKeys::FirstWins {
+                break;
+            }
+        }
+        Ok(found)
+    }
+
+    /// The array element at `index`. The elements of an array are found on
+    /// the first call and kept, so that looping over them with `at` stays
+    /// linear.
+    pub fn at(&self, index: usize) -> Result<Option<LazyValue<'b, 'a>>, DecodeError> {
+        let (open, close) = match self.brackets()? {
+            Some((open, close)) if self.lazy.byte(open) != b'{' => (open, close),
+            _ => return Ok(None),
+        };
+        let mut cache = self.lazy.elements.lock().unwrap();
+        let elements = match cache.entry(open) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let filled = self.filled(open, close)?;
+                entry.insert(filled.iter().map(|v| (v.start, v.end, v.entry)).collect())
+            }
+        };
+        Ok(elements.get(index).map(|&(start, end, entry)| {
+            LazyValue {
+                lazy: self.lazy,
+                start,
+                end,
+                entry,
+            }
+        }))
+    }
+
+    pub fn get_path(&self, path: &[PathSegment]) -> Result<Option<LazyValue<'b, 'a>>, DecodeError> {
+        let mut value = *self;
+        for segment in path {
+            let next = match *segment {
+                PathSegment::Key(ref k) => value.get(k)?,
+                PathSegment::Index(i) => value.at(i)?,
+            };
+            value = match next {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(value))
+    }
+
+    /// Decodes the value and everything in it.
+    pub fn decode(&self) -> Result<JFObject, DecodeError> {
+        let text = self.lazy.text;
+        let root = self.end == text.len();
+        if !root && self.token()?.is_none() {
+            return Ok(JFObject::Null);
+        }
+        let mut parser = self.parser();
+        parser.seek(self.start)?;
+        let value = build(&mut parser, |_, _| {})?;
+        parser.seek(parser.offset())?;
+        let rest = parser.offset();
+        if rest < self.end {
+            let kind = if root {
+                ErrorKind::TrailingCharacters
+            } else {
+                ErrorKind::UnexpectedCharacter(text[rest..].chars().next().unwrap())
+            };
+            return Err(DecodeError::new(kind, text, rest));
+        }
+        Ok(value)
+    }
+}
+
+fn partner(open: u8) -> u8 {
+    match open {
+        b'{' => b'}',
+        b'[' => b']',
+        _ => b')',
+    }
+}
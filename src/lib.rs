@@ -1,9 +1,59 @@
 extern crate rustc_serialize;
+#[cfg(feature = "tokio")]
+extern crate futures_core;
+#[cfg(feature = "tokio")]
+extern crate tokio;
 
+#[cfg(feature = "tokio")]
+mod async_decode;
+mod comment;
+mod cst;
+mod document;
+mod error;
+mod find;
+mod incremental;
 mod json_flex;
+mod lazy;
+mod limits;
+mod number;
+mod options;
+mod parallel;
+mod parser;
+mod path;
+mod reader;
+mod recover;
+mod repair;
+mod report;
+mod spanned;
+mod structural;
+mod value;
+mod visitor;
+#[cfg(feature = "tokio")]
+pub use async_decode::{decode_async, ndjson_stream, DecodeAsync, NdjsonStream, NextValue};
 pub use json_flex::decode;
 pub use json_flex::Unwrap;
 pub use json_flex::JFObject;
+pub use comment::{decode_with_comments, Comment, CommentKind, Placement};
+pub use cst::{Cst, CstKind, CstNode};
+pub use document::{Document, Elements, Members, NodeRef};
+pub use error::{DecodeError, ErrorKind, Position};
+pub use limits::{Limit, ParseLimits};
+pub use lazy::{Lazy, LazyValue};
+pub use find::{find_json, find_json_with, unwrap_jsonp, Found};
+pub use incremental::IncrementalParser;
+pub use number::{Decimal, Number};
+pub use options::{ArrayHoles, DuplicateKeys, Options};
+pub use parallel::{decode_ndjson_parallel, decode_parallel};
+pub use parser::{decode_with, Event, Parser};
+pub use path::PathSegment;
+pub use reader::ReaderParser;
+pub use recover::{recover, recover_with};
+pub use repair::{repair, repair_with, Repair, RepairKind};
+pub use report::{decode_with_report, Warning, WarningKind};
+pub use spanned::{decode_spanned, Span, Spanned, SpannedValue};
+pub use structural::{structural_index, structural_index_with, ScanBackend};
+pub use value::{decode_borrowed, JFValue};
+pub use visitor::{decode_into, visit, JFObjectBuilder, Visitor};
 
 #[cfg(test)]
-mod test;
\ No newline at end of file
+mod test;
@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// Upper bounds enforced by `decode_with`; `None` means unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseLimits {
+    /// Arrays and objects open at the same time.
+    pub max_depth: Option<usize>,
+    /// Bytes in a decoded string or key.
+    pub max_string_length: Option<usize>,
+    /// Bytes in the source text of a number.
+    pub max_number_length: Option<usize>,
+    /// Values in the document, containers included.
+    pub max_nodes: Option<usize>,
+    pub max_input_bytes: Option<usize>,
+}
+
+impl ParseLimits {
+    /// Bounds for input from untrusted sources: depth 128, 1 MiB strings,
+    /// 100 digit numbers, a million values and 64 MiB of input.
+    pub fn untrusted() -> ParseLimits {
+        ParseLimits {
+            max_depth: Some(128),
+            max_string_length: Some(1 << 20),
+            max_number_length: Some(100),
+            max_nodes: Some(1_000_000),
+            max_input_bytes: Some(64 << 20),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Depth,
+    StringLength,
+    NumberLength,
+    Nodes,
+    InputBytes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Limit::Depth => "nesting depth",
+            Limit::StringLength => "string length",
+            Limit::NumberLength => "number length",
+            Limit::Nodes => "number of values",
+            Limit::InputBytes => "input size",
+        };
+        f.write_str(s)
+    }
+}
@@ -0,0 +1,199 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// A number kept as written, from `decode_with` with `Options::raw_numbers`.
+/// Nothing is lost until one of the accessors converts it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Number {
+    lexeme: String,
+}
+
+impl Number {
+    /// Only called with lexemes the parser accepted as numbers.
+    pub(crate) fn from_lexeme(lexeme: &str) -> Number {
+        Number { lexeme: lexeme.to_owned() }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.lexeme
+    }
+
+    /// The value if it is an integer in range, written without a fraction or
+    /// exponent.
+    pub fn as_i64(&self) -> Option<i64> {
+        let (negative, digits) = self.sign_and_digits();
+        if let Some(hex) = hex_digits(digits) {
+            let u = u64::from_str_radix(hex, 16).ok()?;
+            return if negative {
+                0i64.checked_sub_unsigned(u)
+            } else {
+                i64::try_from(u).ok()
+            };
+        }
+        i64::from_str(self.lexeme.trim_start_matches('+')).ok()
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        let (negative, digits) = self.sign_and_digits();
+        let u = match hex_digits(digits) {
+            Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+            None => u64::from_str(digits).ok()?,
+        };
+        if negative && u != 0 {
+            return None;
+        }
+        Some(u)
+    }
+
+    /// The nearest `f64`, which may round.
+    pub fn as_f64(&self) -> f64 {
+        let (negative, digits) = self.sign_and_digits();
+        let f = match hex_digits(digits) {
+            Some(hex) => {
+                hex.chars().fold(0.0, |acc, c| acc * 16.0 + c.to_digit(16).unwrap() as f64)
+            }
+            None => f64::from_str(digits).unwrap_or(f64::NAN),
+        };
+        if negative { -f } else { f }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        let (_, digits) = self.sign_and_digits();
+        hex_digits(digits).is_some() || digits.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    fn sign_and_digits(&self) -> (bool, &str) {
+        match self.lexeme.as_bytes().first() {
+            Some(b'-') => (true, &self.lexeme[1..]),
+            Some(b'+') => (false, &self.lexeme[1..]),
+            _ => (false, &self.lexeme),
+        }
+    }
+}
+
+fn hex_digits(digits: &str) -> Option<&str> {
+    if digits.starts_with("0x") || digits.starts_with("0X") {
+        Some(&digits[2..])
+    } else {
+        None
+    }
+}
+
+impl From<i64> for Number {
+    fn from(i: i64) -> Number {
+        Number { lexeme: i.to_string() }
+    }
+}
+
+impl From<u64> for Number {
+    fn from(u: u64) -> Number {
+        Number { lexeme: u.to_string() }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.lexeme)
+    }
+}
+
+/// An exact decimal, `mantissa × 10^-scale`, from `decode_with` with
+/// `Options::decimals`. The scale keeps trailing zeros, so `0.1000` has
+/// mantissa 1000 and scale 4, and is at most `Decimal::MAX_SCALE`. A zero
+/// keeps its sign, so `-0.0` stays `-0.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    mantissa: i64,
+    scale: u32,
+    /// Whether the number was written with a minus sign; only differs from
+    /// `mantissa < 0` for zero.
+    negative: bool,
+}
+
+impl Decimal {
+    pub const MAX_SCALE: u32 = 38;
+
+    /// Panics if `scale` is over `MAX_SCALE`.
+    pub fn new(mantissa: i64, scale: u32) -> Decimal {
+        assert!(scale <= Decimal::MAX_SCALE, "decimal scale {} is too large", scale);
+        Decimal {
+            mantissa,
+            scale,
+            negative: mantissa < 0,
+        }
+    }
+
+    /// Parses JSON number syntax; `None` if the digits do not fit in an
+    /// `i64` mantissa or the scale would be over `MAX_SCALE`. Zero takes
+    /// any exponent.
+    pub(crate) fn from_lexeme(lexeme: &str) -> Option<Decimal> {
+        let (mantissa_part, exponent) = match lexeme.find(['e', 'E']) {
+            Some(i) => {
+                let exponent = lexeme[i + 1..].trim_start_matches('+');
+                (&lexeme[..i], i64::from_str(exponent).ok()?)
+            }
+            None => (lexeme, 0),
+        };
+        let (int, frac) = match mantissa_part.find('.') {
+            Some(i) => (&mantissa_part[..i], &mantissa_part[i + 1..]),
+            None => (mantissa_part, ""),
+        };
+        let digits = format!("{}{}", int.trim_start_matches('+'), frac);
+        let mut mantissa = i64::from_str(&digits).ok()?;
+        let mut scale = (frac.len() as i64).checked_sub(exponent)?;
+        if mantissa == 0 {
+            scale = scale.clamp(0, Decimal::MAX_SCALE as i64);
+        }
+        while scale < 0 {
+            mantissa = mantissa.checked_mul(10)?;
+            scale += 1;
+        }
+        let scale = u32::try_from(scale).ok().filter(|&s| s <= Decimal::MAX_SCALE)?;
+        Some(Decimal {
+            mantissa,
+            scale,
+            negative: int.starts_with('-'),
+        })
+    }
+
+    pub fn mantissa(&self) -> i64 {
+        self.mantissa
+    }
+
+    /// Digits after the decimal point.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Whether the decimal is negative, `-0` included.
+    pub fn is_sign_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The nearest `f64`, which may round.
+    pub fn to_f64(&self) -> f64 {
+        f64::from_str(&self.to_string()).unwrap()
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let digits = if digits.len() <= scale {
+            format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits)
+        } else {
+            digits
+        };
+        if self.negative {
+            f.write_str("-")?;
+        }
+        let point = digits.len() - scale;
+        if scale == 0 {
+            f.write_str(&digits)
+        } else {
+            write!(f, "{}.{}", &digits[..point], &digits[point..])
+        }
+    }
+}
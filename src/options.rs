@@ -0,0 +1,151 @@
+use limits::ParseLimits;
+
+/// What to do with a key that appears more than once in an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Keep the last value, like `decode`.
+    LastWins,
+    FirstWins,
+    /// Fail with `ErrorKind::DuplicateKey`.
+    Error,
+    /// Collect every value for the key into an array, in input order.
+    Collect,
+}
+
+/// What an empty array slot such as the middle of `[1,,2]` stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayHoles {
+    /// A syntax error.
+    Reject,
+    /// `JFObject::Null`, like `decode`. Together with `trailing_commas` this
+    /// follows JavaScript: `[1,,]` has two elements and `[,]` one.
+    Null,
+    /// The slot is left out, so `[1,,2]` decodes like `[1,2]`.
+    Skip,
+}
+
+/// Switches for the non-standard syntax accepted by `decode_with`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// `'single quoted'` strings and keys.
+    pub single_quotes: bool,
+    /// A comma directly before `]` or `}` is ignored.
+    pub trailing_commas: bool,
+    /// Empty array slots such as `[1,,2]` or `[,]`.
+    pub array_holes: ArrayHoles,
+    /// `// line` and `/* block */` comments.
+    pub comments: bool,
+    /// Identifier keys such as `{foo: 1}` or `{0: 1}`.
+    pub unquoted_keys: bool,
+    /// Unquoted words such as `{status: active}` decode to strings; `true`,
+    /// `false` and `null` keep their meaning.
+    pub bareword_values: bool,
+    /// JSON5 numbers, strings and whitespace: hexadecimal, leading or trailing
+    /// decimal points, explicit `+`, `Infinity`, `NaN`, `\x`/`\v`/`\0` escapes,
+    /// escaped line breaks and Unicode space separators.
+    pub json5: bool,
+    /// Python `repr()` output: `True`, `False`, `None`, `nan`, `inf`, `\x`
+    /// escapes and `( )` tuples, which decode to arrays.
+    pub python_literals: bool,
+    /// JavaScript values: `undefined` decodes to `JFObject::Null`, `NaN` and
+    /// `Infinity` to `JFObject::Float`.
+    pub javascript_literals: bool,
+    /// Numbers written with digits decode to `JFObject::Number`, keeping
+    /// their exact text, instead of `Integer` or `Float`.
+    pub raw_numbers: bool,
+    /// Numbers with a fraction or exponent, and integers too large for `i64`,
+    /// decode to an exact `JFObject::Decimal` when the digits fit one.
+    pub decimals: bool,
+    pub duplicate_keys: DuplicateKeys,
+    pub limits: ParseLimits,
+}
+
+impl Options {
+    /// The JSON5 grammar (https://spec.json5.org).
+    pub fn json5() -> Options {
+        Options {
+            trailing_commas: true,
+            array_holes: ArrayHoles::Reject,
+            comments: true,
+            unquoted_keys: true,
+            json5: true,
+            ..Options::default()
+        }
+    }
+
+    /// JSON with `//` and `/* */` comments and trailing commas, as used by
+    /// VS Code `.jsonc` settings files.
+    pub fn jsonc() -> Options {
+        Options {
+            single_quotes: false,
+            trailing_commas: true,
+            array_holes: ArrayHoles::Reject,
+            comments: true,
+            ..Options::default()
+        }
+    }
+
+    /// Dicts, lists and tuples as printed by Python's `repr()`.
+    pub fn python() -> Options {
+        Options {
+            trailing_commas: true,
+            array_holes: ArrayHoles::Reject,
+            python_literals: true,
+            ..Options::default()
+        }
+    }
+
+    /// Object literals as printed by `console.log` or written in JavaScript
+    /// source.
+    pub fn javascript() -> Options {
+        Options {
+            trailing_commas: true,
+            comments: true,
+            unquoted_keys: true,
+            json5: true,
+            javascript_literals: true,
+            ..Options::default()
+        }
+    }
+
+    /// Everything this crate can make sense of, for JavaScript-ish payloads
+    /// such as `{id: 3, status: active,}`.
+    pub fn lenient() -> Options {
+        Options {
+            single_quotes: true,
+            trailing_commas: true,
+            array_holes: ArrayHoles::Null,
+            comments: true,
+            unquoted_keys: true,
+            bareword_values: true,
+            json5: true,
+            python_literals: true,
+            javascript_literals: true,
+            raw_numbers: false,
+            decimals: false,
+            duplicate_keys: DuplicateKeys::LastWins,
+            limits: ParseLimits::default(),
+        }
+    }
+}
+
+impl Default for Options {
+    /// The same leniencies as `decode`: single quotes and array holes.
+    fn default() -> Options {
+        Options {
+            single_quotes: true,
+            trailing_commas: false,
+            array_holes: ArrayHoles::Null,
+            comments: false,
+            unquoted_keys: false,
+            bareword_values: false,
+            json5: false,
+            python_literals: false,
+            javascript_literals: false,
+            raw_numbers: false,
+            decimals: false,
+            duplicate_keys: DuplicateKeys::LastWins,
+            limits: ParseLimits::default(),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use error::{DecodeError, ErrorKind};
+use json_flex::JFObject;
+use limits::Limit;
+use options::Options;
+use parser::{decode_with, Parser};
+use structural::structural_index;
+use visitor::build;
+
+/// Decodes every non-blank line of `text` as its own document, spread over
+/// `threads` threads (0 for one per CPU). Results are in input order and
+/// error positions refer to `text`.
+pub fn decode_ndjson_parallel(text: &str,
+                              options: &Options,
+                              threads: usize)
+                              -> Vec<Result<JFObject, DecodeError>> {
+    if let Err(e) = check_size(text, options) {
+        return vec![Err(e)];
+    }
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for end in text.match_indices('\n').map(|m| m.0).chain(Some(text.len())) {
+        if !text[start..end].trim().is_empty() {
+            lines.push(start..end);
+        }
+        start = end + 1;
+    }
+    in_parallel(&lines, threads, |line| decode_range(text, options, line))
+}
+
+/// Like `decode_with`, decoding the elements of a top-level array on
+/// `threads` threads (0 for one per CPU). Anything else, and arrays with
+/// holes or a trailing comma, are decoded on the calling thread, as is all
+/// input when `max_nodes` is limited.
+pub fn decode_parallel(text: String,
+                       options: &Options,
+                       threads: usize)
+                       -> Result<Box<JFObject>, DecodeError> {
+    let elements = match split_array(&text, options) {
+        Some(elements) => elements,
+        None => return decode_with(text, options),
+    };
+    // Elements start one level down.
+    let mut inner = options.clone();
+    inner.limits.max_depth = options.limits.max_depth.map(|max| max.saturating_sub(1));
+    let mut values = Vec::with_capacity(elements.len());
+    for value in in_parallel(&elements, threads, |e| decode_range(&text, &inner, e)) {
+        match value {
+            Ok(value) => values.push(value),
+            // An element on its own lacks the array around it, which the
+            // error and its hint depend on: report what `decode_with` does.
+            Err(e) => return decode_with(text, options).and(Err(e)),
+        }
+    }
+    Ok(Box::new(JFObject::Array(values)))
+}
+
+fn check_size(text: &str, options: &Options) -> Result<(), DecodeError> {
+    if options.limits.max_input_bytes.is_some_and(|max| text.len() > max) {
+        return Err(DecodeError::new(ErrorKind::LimitExceeded(Limit::InputBytes), text, 0));
+    }
+    Ok(())
+}
+
+/// The byte ranges of the elements of a top-level array, or `None` when
+/// the input should be decoded in one piece.
+fn split_array(text: &str, options: &Options) -> Option<Vec<Range<usize>>> {
+    if options.limits.max_nodes.is_some() || check_size(text, options).is_err() {
+        return None;
+    }
+    let positions = structural_index(text, options).ok()?;
+    let open = *positions.first()? as usize;
+    let mut parser = Parser::new(text, options.clone());
+    if parser.seek(0).ok()? != Some(b'[') || parser.offset() != open {
+        return None;
+    }
+
+    let bytes = text.as_bytes();
+    let mut elements = Vec::new();
+    let mut start = open + 1;
+    let mut depth = 0;
+    let mut close = None;
+    for &p in &positions[1..] {
+        let p = p as usize;
+        match bytes[p] {
+            b'[' | b'{' | b'(' => depth += 1,
+            b']' if depth == 0 => {
+                elements.push(start..p);
+                close = Some(p);
+                break;
+            }
+            b']' | b'}' | b')' => depth -= 1,
+            b',' if depth == 0 => {
+                elements.push(start..p);
+                start = p + 1;
+            }
+            _ => {}
+        }
+    }
+
+    // Past the closing bracket there must be nothing but trivia, and every
+    // element must hold a value; `decode_with` reports anything else.
+    if parser.seek(close? + 1).ok()?.is_some() {
+        return None;
+    }
+    for element in &elements {
+        let value = parser.seek(element.start).ok()?;
+        if value.is_none() || parser.offset() >= element.end {
+            if elements.len() == 1 {
+                return Some(Vec::new());
+            }
+            return None;
+        }
+    }
+    Some(elements)
+}
+
+/// Decodes the value in `range`, reporting positions in `text`.
+fn decode_range(text: &str,
+                options: &Options,
+                range: &Range<usize>)
+                -> Result<JFObject, DecodeError> {
+    let mut parser = Parser::new(&text[..range.end], options.clone());
+    parser.seek(range.start)
+        .and_then(|_| {
+            parser.forbid_trailing();
+            build(&mut parser, |_, _| {})
+        })
+        .map_err(|e| in_context(e, text))
+}
+
+/// `error` with its source line taken from all of `text` rather than the
+/// part that was parsed.
+fn in_context(error: DecodeError, text: &str) -> DecodeError {
+    DecodeError {
+        expected: error.expected,
+        hint: error.hint,
+        ..DecodeError::new(error.kind, text, error.position.offset)
+    }
+}
+
+/// Maps `jobs` with `decode` on a scoped pool, handing out batches from a
+/// shared counter, and returns the results in job order.
+fn in_parallel<T, F>(jobs: &[Range<usize>], threads: usize, decode: F) -> Vec<T>
+    where T: Send,
+          F: Fn(&Range<usize>) -> T + Sync
+{
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let threads = cmp::max(1, cmp::min(threads, jobs.len()));
+    if threads == 1 {
+        return jobs.iter().map(decode).collect();
+    }
+    let batch = cmp::max(1, jobs.len() / (threads * 8));
+    let next = AtomicUsize::new(0);
+    let mut batches: Vec<(usize, Vec<T>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let start = next.fetch_add(batch, Ordering::Relaxed);
+                        if start >= jobs.len() {
+                            return done;
+                        }
+                        let end = cmp::min(start + batch, jobs.len());
+                        done.push((start, jobs[start..end].iter().map(&decode).collect()));
+                    }
+                })
+            })
+            .collect();
+        workers.into_iter().flat_map(|w| w.join().unwrap()).collect()
+    });
+    batches.sort_by_key(|b| b.0);
+    batches.into_iter().flat_map(|b| b.1).collect()
+}
@@ -0,0 +1,1398 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::char;
+use std::f64;
+use std::io::Read;
+use std::ops::Range;
+use std::str::FromStr;
+
+use error::{DecodeError, ErrorKind, Position};
+use json_flex::JFObject;
+use limits::Limit;
+use number::Decimal;
+use options::{ArrayHoles, DuplicateKeys, Options};
+use reader::ReaderParser;
+use repair::{Repair, RepairKind};
+use report::{Warning, WarningKind};
+use structural::string_index;
+use visitor::{visit, JFObjectBuilder, Visitor};
+
+/// One step through a document. Containers open and close around their
+/// contents; in an object every value follows its `Key`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(Cow<'a, str>),
+    String(Cow<'a, str>),
+    Integer(i64),
+    Float(f64),
+    /// A number as written, with `Options::raw_numbers`.
+    Number(Cow<'a, str>),
+    /// A number that is not an `i64`, with `Options::decimals`.
+    Decimal(Decimal),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frame {
+    Array,
+    Tuple,
+    Object,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Root,
+    RootEnd,
+    ArrayFirst,
+    ArrayElement,
+    ArrayNext,
+    ObjectFirst,
+    ObjectKey,
+    ObjectValue,
+    ObjectNext,
+    Repairing,
+    Recovering,
+    Done,
+}
+
+/// A pull parser over `text`, yielding one `Event` per call to `next_event`
+/// without building anything, and accepting what `options` allows just as
+/// `decode_with` does. `token` and `position` say where the last event came
+/// from. `from_reader` parses input that is read as it goes.
+#[derive(Clone)]
+pub struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    options: Options,
+    stack: Vec<Frame>,
+    state: State,
+    retain_comments: bool,
+    comments: Vec<(usize, usize)>,
+    repair: bool,
+    repairs: Vec<Repair>,
+    recover: bool,
+    errors: Vec<DecodeError>,
+    key_start: usize,
+    key_end: usize,
+    start: usize,
+    token: Range<usize>,
+    comma: usize,
+    nodes: usize,
+    allow_trailing: bool,
+    report: bool,
+    warnings: Vec<Warning>,
+    /// The last position worked out by `position`.
+    located: Cell<Position>,
+    /// Line and column of the start of `text`, which for `ReaderParser` is
+    /// not the start of the input.
+    origin: Position,
+    /// Offsets from `string_index`, if `index_strings` was called, and how
+    /// far `string` has looked through them.
+    index: Vec<u32>,
+    indexed: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(text: &'a str, options: Options) -> Parser<'a> {
+        Parser {
+            text,
+            bytes: text.as_bytes(),
+            pos: 0,
+            options,
+            stack: Vec::new(),
+            state: State::Root,
+            retain_comments: false,
+            comments: Vec::new(),
+            repair: false,
+            repairs: Vec::new(),
+            recover: false,
+            errors: Vec::new(),
+            key_start: 0,
+            key_end: 0,
+            start: 0,
+            token: 0..0,
+            comma: 0,
+            nodes: 0,
+            allow_trailing: false,
+            report: false,
+            warnings: Vec::new(),
+            located: Cell::new(Position::from_offset(text, 0)),
+            origin: Position::from_offset(text, 0),
+            index: Vec::new(),
+            indexed: 0,
+        }
+    }
+
+    /// A parser reading `reader` as it goes; see `ReaderParser`.
+    pub fn from_reader<R: Read>(reader: R, options: Options) -> ReaderParser<R> {
+        ReaderParser::new(reader, options)
+    }
+
+    /// The parser with its state carried over to `text`, which is the
+    /// current text with its first `shift` bytes dropped and maybe more
+    /// added, and starts at `origin`.
+    pub(crate) fn moved(self, text: &str, shift: usize, origin: Position) -> Parser<'_> {
+        let located = if shift == 0 { self.located.get() } else { origin };
+        Parser {
+            text,
+            bytes: text.as_bytes(),
+            pos: self.pos - shift,
+            key_start: self.key_start.saturating_sub(shift),
+            key_end: self.key_end.saturating_sub(shift),
+            start: self.start.saturating_sub(shift),
+            token: self.token.start.saturating_sub(shift)..self.token.end.saturating_sub(shift),
+            comma: self.comma.saturating_sub(shift),
+            located: Cell::new(located),
+            origin,
+            ..self
+        }
+    }
+
+    /// Finds the double-quoted strings up front with the structural scan, so
+    /// that those without escapes are taken whole rather than byte by byte.
+    /// Input the scan rejects is left to the byte loop, for its errors.
+    pub(crate) fn index_strings(&mut self) {
+        let max = self.options.limits.max_input_bytes;
+        if max.map_or(true, |max| self.text.len() <= max) {
+            self.index = string_index(self.text, &self.options).unwrap_or_default();
+        }
+    }
+
+    /// Stops after the first value instead of requiring the rest of the input
+    /// to be blank.
+    pub(crate) fn allow_trailing(&mut self) {
+        self.allow_trailing = true;
+    }
+
+    /// Requires the rest of the input to be blank after the next value read,
+    /// as `decode_with` does, which `seek` does not.
+    pub(crate) fn forbid_trailing(&mut self) {
+        self.allow_trailing = false;
+    }
+
+    /// Turns errors into fixes recorded for `take_repairs`, so that every
+    /// input yields a value. A `Key` whose value turns out to be missing is
+    /// then followed directly by the next `Key` or `EndObject`.
+    pub(crate) fn repair_mode(&mut self) {
+        self.repair = true;
+    }
+
+    pub(crate) fn take_repairs(&mut self) -> Vec<Repair> {
+        ::std::mem::take(&mut self.repairs)
+    }
+
+    /// Records errors for `take_errors` and carries on after the next `,` or
+    /// closing bracket of an open container, leaving out the element or
+    /// member that failed.
+    pub(crate) fn recover_mode(&mut self) {
+        self.recover = true;
+    }
+
+    pub(crate) fn take_errors(&mut self) -> Vec<DecodeError> {
+        ::std::mem::take(&mut self.errors)
+    }
+
+    /// Records every non-standard construct accepted for `take_warnings`.
+    pub(crate) fn report_warnings(&mut self) {
+        self.report = true;
+    }
+
+    pub(crate) fn take_warnings(&mut self) -> Vec<Warning> {
+        ::std::mem::take(&mut self.warnings)
+    }
+
+    /// Keeps the byte ranges of skipped comments for `take_comments`.
+    pub(crate) fn retain_comments(&mut self) {
+        self.retain_comments = true;
+    }
+
+    /// Comments skipped since the previous call.
+    pub(crate) fn take_comments(&mut self) -> Vec<(usize, usize)> {
+        ::std::mem::take(&mut self.comments)
+    }
+
+    /// Byte offset just past the token behind the last event.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Source range of the last event: the key, the scalar, the opening or
+    /// the closing bracket. Empty for holes and for brackets added by repair.
+    pub fn token(&self) -> Range<usize> {
+        self.token.clone()
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Line and column of the start of `token()`, counted on from the
+    /// previous call so that asking after every event stays linear.
+    pub fn position(&self) -> Position {
+        self.locate(self.token.start)
+    }
+
+    /// Line and column of `offset`, counted on from the last position found
+    /// when it is further on, as it is for repairs, warnings and errors.
+    fn locate(&self, offset: usize) -> Position {
+        let mut p = self.located.get();
+        if offset < p.offset {
+            p = self.origin;
+        }
+        for c in self.text[p.offset..offset].chars() {
+            if c == '\n' {
+                p.line += 1;
+                p.column = 1;
+            } else {
+                p.column += 1;
+            }
+        }
+        p.offset = offset;
+        self.located.set(p);
+        p
+    }
+
+    /// Starts over at the first token at or after `offset`, expecting a
+    /// single value there, and returns the token's first byte.
+    pub(crate) fn seek(&mut self, offset: usize) -> Result<Option<u8>, DecodeError> {
+        self.pos = offset;
+        self.stack.clear();
+        self.state = State::Root;
+        self.allow_trailing = true;
+        self.skip_trivia()?;
+        Ok(self.peek())
+    }
+
+    /// Reads an object key at the current position, as after `seek`.
+    pub(crate) fn read_key(&mut self) -> Result<Cow<'a, str>, DecodeError> {
+        let start = self.pos;
+        let key = self.key()?;
+        self.check_string(&key, start)?;
+        Ok(key)
+    }
+
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>, DecodeError> {
+        loop {
+            match self.advance() {
+                Err(e) if matches!(e.kind, ErrorKind::LimitExceeded(_)) => return Err(e),
+                Err(e) if self.repair => return Ok(self.abandon(e)),
+                Err(e) if self.recover => {
+                    self.pos = self.pos.max(e.position.offset);
+                    self.errors.push(e);
+                    self.state = State::Recovering;
+                }
+                Ok(Some(event)) => {
+                    self.token = match event {
+                        Event::Key(_) => self.key_start..self.key_end,
+                        _ => self.start..self.pos,
+                    };
+                    if !matches!(event, Event::Key(_) | Event::EndArray | Event::EndObject) {
+                        self.nodes += 1;
+                        if self.options.limits.max_nodes.is_some_and(|max| self.nodes > max) {
+                            let kind = ErrorKind::LimitExceeded(Limit::Nodes);
+                            return Err(self.error(kind, self.pos));
+                        }
+                    }
+                    return Ok(Some(event));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Result<Option<Event<'a>>, DecodeError> {
+        loop {
+            match self.state {
+                State::Done => return Ok(None),
+
+                State::Repairing => {
+                    return Ok(match self.stack.pop() {
+                        Some(frame) => Some(self.closed(frame)),
+                        None => {
+                            self.state = State::Done;
+                            None
+                        }
+                    });
+                }
+
+                State::Recovering => {
+                    if let Some(event) = self.resync() {
+                        return Ok(Some(event));
+                    }
+                }
+
+                State::Root | State::ObjectValue => {
+                    let max_input = self.options.limits.max_input_bytes;
+                    if self.state == State::Root && self.pos == 0 &&
+                       max_input.is_some_and(|max| self.bytes.len() > max) {
+                        return Err(self.error(ErrorKind::LimitExceeded(Limit::InputBytes), 0));
+                    }
+                    self.skip_trivia()?;
+                    if self.repair && self.state == State::ObjectValue &&
+                       (self.peek() == Some(b',') || self.peek() == Some(b'}')) {
+                        let key_start = self.key_start;
+                        self.log(RepairKind::DroppedKey, key_start);
+                        self.state = State::ObjectNext;
+                        continue;
+                    }
+                    return self.value().map(Some);
+                }
+
+                State::RootEnd if self.allow_trailing => {
+                    self.state = State::Done;
+                    return Ok(None);
+                }
+
+                State::RootEnd => {
+                    self.skip_trivia()?;
+                    if self.pos < self.bytes.len() {
+                        if self.repair {
+                            self.log(RepairKind::DroppedInput, self.pos);
+                            self.pos = self.bytes.len();
+                            continue;
+                        }
+                        return Err(self.error(ErrorKind::TrailingCharacters, self.pos));
+                    }
+                    self.state = State::Done;
+                    return Ok(None);
+                }
+
+                State::ArrayFirst | State::ArrayElement => {
+                    self.skip_trivia()?;
+                    let close = self.closing_bracket();
+                    match self.peek() {
+                        Some(b) if b == close &&
+                                   (self.state == State::ArrayFirst ||
+                                    self.options.trailing_commas) => {
+                            if self.state == State::ArrayElement {
+                                let comma = self.comma;
+                                self.warn(WarningKind::TrailingComma, comma, comma + 1);
+                            }
+                            return Ok(Some(self.end_container()));
+                        }
+                        Some(b) if (b == close || b == b',') &&
+                                   self.options.array_holes != ArrayHoles::Reject => {
+                            let pos = self.pos;
+                            self.warn(WarningKind::ArrayHole, pos, pos + 1);
+                            self.start = pos;
+                            self.state = State::ArrayNext;
+                            if self.options.array_holes == ArrayHoles::Null {
+                                return Ok(Some(Event::Null));
+                            }
+                        }
+                        _ => return self.value().map(Some),
+                    }
+                }
+
+                State::ArrayNext => {
+                    self.skip_trivia()?;
+                    match self.peek() {
+                        Some(b',') => {
+                            self.comma = self.pos;
+                            self.pos += 1;
+                            self.state = State::ArrayElement;
+                        }
+                        Some(b) if b == self.closing_bracket() => {
+                            return Ok(Some(self.end_container()))
+                        }
+                        Some(_) if self.repair => {
+                            if let Some(event) = self.repair_separator(State::ArrayElement) {
+                                return Ok(Some(event));
+                            }
+                        }
+                        _ => return Err(self.unexpected()),
+                    }
+                }
+
+                State::ObjectFirst | State::ObjectKey => {
+                    self.skip_trivia()?;
+                    match self.peek() {
+                        Some(b'}') if self.state == State::ObjectFirst ||
+                                      self.options.trailing_commas => {
+                            if self.state == State::ObjectKey {
+                                let comma = self.comma;
+                                self.warn(WarningKind::TrailingComma, comma, comma + 1);
+                            }
+                            return Ok(Some(self.end_container()));
+                        }
+                        _ => {
+                            self.key_start = self.pos;
+                            let key = self.key()?;
+                            self.key_end = self.pos;
+                            self.check_string(&key, self.key_start)?;
+                            self.skip_trivia()?;
+                            match self.peek() {
+                                Some(b':') => self.pos += 1,
+                                Some(b',') | Some(b'}') if self.repair => {
+                                    let key_start = self.key_start;
+                                    self.log(RepairKind::DroppedKey, key_start);
+                                    self.state = State::ObjectNext;
+                                    continue;
+                                }
+                                Some(_) if self.repair => {
+                                    self.log(RepairKind::InsertedColon, self.pos)
+                                }
+                                _ => return Err(self.unexpected_expecting(vec!["':'"])),
+                            }
+                            self.state = State::ObjectValue;
+                            return Ok(Some(Event::Key(key)));
+                        }
+                    }
+                }
+
+                State::ObjectNext => {
+                    self.skip_trivia()?;
+                    match self.peek() {
+                        Some(b',') => {
+                            self.comma = self.pos;
+                            self.pos += 1;
+                            self.state = State::ObjectKey;
+                        }
+                        Some(b'}') => return Ok(Some(self.end_container())),
+                        Some(_) if self.repair => {
+                            if let Some(event) = self.repair_separator(State::ObjectKey) {
+                                return Ok(Some(event));
+                            }
+                        }
+                        _ => return Err(self.unexpected()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles something other than a comma or the expected bracket after a
+    /// value: a bracket closing an outer container ends the inner ones, a
+    /// stray bracket is dropped and anything else gets a comma in front.
+    fn repair_separator(&mut self, resume: State) -> Option<Event<'a>> {
+        let b = self.peek().unwrap();
+        if b == b']' || b == b'}' || b == b')' {
+            if self.stack.iter().any(|f| closing_bracket(*f) == b) {
+                let frame = self.stack.pop().unwrap();
+                let event = self.closed(frame);
+                self.after_value();
+                return Some(event);
+            }
+            self.log(RepairKind::DroppedInput, self.pos);
+            self.pos += 1;
+        } else {
+            self.log(RepairKind::InsertedComma, self.pos);
+            self.state = resume;
+        }
+        None
+    }
+
+    /// Gives up on the rest of the input after `error` and closes whatever is
+    /// still open.
+    fn abandon(&mut self, error: DecodeError) -> Option<Event<'a>> {
+        let offset = error.position.offset;
+        if offset < self.bytes.len() {
+            self.log(RepairKind::DroppedInput, offset);
+        }
+        self.pos = self.bytes.len();
+        match self.state {
+            State::Root => {
+                self.state = State::Done;
+                return Some(Event::Null);
+            }
+            State::ObjectValue => {
+                let key_start = self.key_start;
+                self.log(RepairKind::DroppedKey, key_start);
+            }
+            _ => {}
+        }
+        self.state = State::Repairing;
+        self.next_event().unwrap_or(None)
+    }
+
+    /// Skips to the next `,` or closing bracket of an open container, outside
+    /// of strings, comments and nested brackets. Containers left open by a
+    /// bracket that closes an outer one, or by the end of the input, are
+    /// ended one event at a time.
+    fn resync(&mut self) -> Option<Event<'a>> {
+        let mut depth = 0;
+        while let Some(b) = self.peek() {
+            let closer = b == b']' || b == b'}' || (b == b')' && self.options.python_literals);
+            match b {
+                b'"' => self.skip_string(b),
+                b'\'' if self.options.single_quotes => self.skip_string(b),
+                b'/' if self.at_comment() => {
+                    if self.comment().is_err() {
+                        self.pos = self.bytes.len();
+                    }
+                }
+                b'[' | b'{' => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                b'(' if self.options.python_literals => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                _ if closer && depth > 0 => {
+                    depth -= 1;
+                    self.pos += 1;
+                }
+                _ if self.stack.is_empty() => self.pos += 1,
+                b',' => {
+                    self.after_value();
+                    return None;
+                }
+                _ if closer && b == self.closing_bracket() => {
+                    self.after_value();
+                    return None;
+                }
+                _ if closer && self.stack.iter().any(|f| closing_bracket(*f) == b) => {
+                    return Some(self.end_frame());
+                }
+                _ => self.pos += 1,
+            }
+        }
+
+        if self.stack.is_empty() {
+            self.state = State::Done;
+            return None;
+        }
+        let end = self.bytes.len();
+        if self.errors.last().map_or(true, |e| e.position.offset < end) {
+            let error = self.error(ErrorKind::UnexpectedEndOfInput, end);
+            self.errors.push(error);
+        }
+        Some(self.end_frame())
+    }
+
+    /// Pops the innermost container while recovering, staying in that state.
+    fn end_frame(&mut self) -> Event<'a> {
+        self.start = self.pos;
+        match self.stack.pop() {
+            Some(Frame::Object) => Event::EndObject,
+            _ => Event::EndArray,
+        }
+    }
+
+    /// Moves past a string, stopping at a line break when it is not closed.
+    fn skip_string(&mut self, quote: u8) {
+        self.pos += 1;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            if b == quote {
+                return;
+            } else if b == b'\\' {
+                self.pos = (self.pos + 1).min(self.bytes.len());
+            } else if b == b'\n' {
+                return;
+            }
+        }
+    }
+
+    /// The end event for a container whose closing bracket is missing.
+    fn closed(&mut self, frame: Frame) -> Event<'a> {
+        let pos = self.pos;
+        self.start = pos;
+        match frame {
+            Frame::Object => {
+                self.log(RepairKind::ClosedObject, pos);
+                Event::EndObject
+            }
+            _ => {
+                self.log(RepairKind::ClosedArray, pos);
+                Event::EndArray
+            }
+        }
+    }
+
+    fn log(&mut self, kind: RepairKind, offset: usize) {
+        let position = self.locate(offset);
+        self.repairs.push(Repair { kind, position });
+    }
+
+    fn warn(&mut self, kind: WarningKind, start: usize, end: usize) {
+        if self.report {
+            let position = self.locate(start);
+            let snippet = self.text[start..end].chars().take(40).collect();
+            self.warnings.push(Warning {
+                kind,
+                position,
+                snippet,
+            });
+        }
+    }
+
+    /// Handles a visitor refusing the last event with `kind`: an error at
+    /// its token, recorded in recover mode and dropped in repair mode. A
+    /// repeated key is a warning, and an error only under
+    /// `DuplicateKeys::Error`.
+    pub(crate) fn refuse(&mut self, kind: ErrorKind) -> Result<(), DecodeError> {
+        let token = self.token();
+        if let ErrorKind::DuplicateKey(_) = kind {
+            self.warn(WarningKind::DuplicateKey, token.start, token.end);
+            if self.options.duplicate_keys != DuplicateKeys::Error {
+                return Ok(());
+            }
+        }
+        let error = self.error(kind, token.start);
+        if self.recover {
+            self.errors.push(error);
+        } else if !self.repair {
+            self.state = State::Done;
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Whether `report_warnings` is on.
+    pub(crate) fn reports_warnings(&self) -> bool {
+        self.report
+    }
+
+    fn value(&mut self) -> Result<Event<'a>, DecodeError> {
+        let c = match self.peek_char() {
+            Some(c) => c,
+            None => return Err(self.unexpected()),
+        };
+        let start = self.pos;
+        self.start = start;
+        let event = match c {
+            '[' => return self.open(Frame::Array),
+            '(' if self.options.python_literals => {
+                self.warn(WarningKind::Tuple, start, start + 1);
+                return self.open(Frame::Tuple);
+            }
+            '{' => return self.open(Frame::Object),
+            '"' => Event::String(self.string(b'"')?),
+            '\'' if self.options.single_quotes => Event::String(self.single_quoted()?),
+            '-' | '0'..='9' => self.number_or_bareword()?,
+            '+' | '.' if self.options.json5 => self.number_or_bareword()?,
+            c if is_ident_start(c) => self.literal()?,
+            c if self.options.bareword_values && !is_structural(c) => {
+                let word = self.bareword();
+                self.warn(WarningKind::Bareword, start, self.pos);
+                Event::String(word)
+            }
+            _ => return Err(self.unexpected()),
+        };
+        if let Event::String(ref s) = event {
+            self.check_string(s, start)?;
+        }
+        self.after_value();
+        Ok(event)
+    }
+
+    fn open(&mut self, frame: Frame) -> Result<Event<'a>, DecodeError> {
+        if self.options.limits.max_depth.is_some_and(|max| self.stack.len() >= max) {
+            return Err(self.error(ErrorKind::LimitExceeded(Limit::Depth), self.pos));
+        }
+        self.pos += 1;
+        self.stack.push(frame);
+        if frame == Frame::Object {
+            self.state = State::ObjectFirst;
+            Ok(Event::StartObject)
+        } else {
+            self.state = State::ArrayFirst;
+            Ok(Event::StartArray)
+        }
+    }
+
+    fn check_string(&self, s: &str, start: usize) -> Result<(), DecodeError> {
+        if self.options.limits.max_string_length.is_some_and(|max| s.len() > max) {
+            return Err(self.error(ErrorKind::LimitExceeded(Limit::StringLength), start));
+        }
+        Ok(())
+    }
+
+    fn after_value(&mut self) {
+        self.state = match self.stack.last() {
+            Some(&Frame::Array) | Some(&Frame::Tuple) => State::ArrayNext,
+            Some(&Frame::Object) => State::ObjectNext,
+            None => State::RootEnd,
+        };
+    }
+
+    fn closing_bracket(&self) -> u8 {
+        self.stack.last().map_or(b']', |f| closing_bracket(*f))
+    }
+
+    fn end_container(&mut self) -> Event<'a> {
+        self.start = self.pos;
+        self.pos += 1;
+        let frame = self.stack.pop();
+        self.after_value();
+        match frame {
+            Some(Frame::Object) => Event::EndObject,
+            _ => Event::EndArray,
+        }
+    }
+
+    fn key(&mut self) -> Result<Cow<'a, str>, DecodeError> {
+        match self.peek_char() {
+            Some('"') => self.string(b'"'),
+            Some('\'') if self.options.single_quotes => self.single_quoted(),
+            Some(c) if self.options.unquoted_keys && (is_ident_part(c) || c == '\\') => {
+                let start = self.pos;
+                let key = self.identifier()?;
+                self.warn(WarningKind::UnquotedKey, start, self.pos);
+                Ok(key)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn single_quoted(&mut self) -> Result<Cow<'a, str>, DecodeError> {
+        let start = self.pos;
+        let s = self.string(b'\'')?;
+        self.warn(WarningKind::SingleQuotes, start, self.pos);
+        Ok(s)
+    }
+
+    fn literal(&mut self) -> Result<Event<'a>, DecodeError> {
+        let start = self.pos;
+        let word = if self.options.bareword_values {
+            self.bareword()
+        } else {
+            self.identifier()?
+        };
+        let python = self.options.python_literals;
+        let javascript = self.options.javascript_literals;
+        if self.repair && self.pos == self.bytes.len() {
+            for literal in &["true", "false", "null"] {
+                if word.len() < literal.len() && literal.starts_with(&*word) {
+                    self.log(RepairKind::CompletedLiteral, start);
+                    return Ok(match *literal {
+                        "true" => Event::Bool(true),
+                        "false" => Event::Bool(false),
+                        _ => Event::Null,
+                    });
+                }
+            }
+        }
+        let (kind, event) = match &*word {
+            "true" => return Ok(Event::Bool(true)),
+            "false" => return Ok(Event::Bool(false)),
+            "null" => return Ok(Event::Null),
+            "True" if python => (WarningKind::Literal, Event::Bool(true)),
+            "False" if python => (WarningKind::Literal, Event::Bool(false)),
+            "None" if python => (WarningKind::Literal, Event::Null),
+            "undefined" if javascript => (WarningKind::Literal, Event::Null),
+            _ => {
+                match self.special_float(&word) {
+                    Some(f) => (WarningKind::NonFiniteNumber, Event::Float(f)),
+                    None if self.options.bareword_values => {
+                        (WarningKind::Bareword, Event::String(word))
+                    }
+                    None => {
+                        let c = word.chars().next().unwrap_or(' ');
+                        return Err(self.error(ErrorKind::UnexpectedCharacter(c), start));
+                    }
+                }
+            }
+        };
+        self.warn(kind, start, self.pos);
+        Ok(event)
+    }
+
+    fn identifier(&mut self) -> Result<Cow<'a, str>, DecodeError> {
+        let start = self.pos;
+        let mut owned: Option<String> = None;
+        while let Some(c) = self.peek_char() {
+            if c == '\\' {
+                let escape_at = self.pos;
+                if self.bytes.get(self.pos + 1) != Some(&b'u') {
+                    return Err(self.error(ErrorKind::InvalidEscape, escape_at));
+                }
+                self.pos += 2;
+                let c = self.unicode_escape(escape_at)?;
+                let mut s = owned.take().unwrap_or_else(|| self.text[start..escape_at].to_owned());
+                s.push(c);
+                owned = Some(s);
+            } else if is_ident_part(c) {
+                if let Some(ref mut s) = owned {
+                    s.push(c);
+                }
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+        Ok(match owned {
+            Some(s) => Cow::Owned(s),
+            None => Cow::Borrowed(&self.text[start..self.pos]),
+        })
+    }
+
+    /// A number, or with `bareword_values` the whole word when something other
+    /// than a delimiter follows it, as in `2021-01-01` or `12:30`.
+    fn number_or_bareword(&mut self) -> Result<Event<'a>, DecodeError> {
+        let start = self.pos;
+        let warnings = self.warnings.len();
+        match self.number() {
+            Err(e) if !self.options.bareword_values ||
+                      matches!(e.kind, ErrorKind::LimitExceeded(_)) => Err(e),
+            Ok(event) if !self.options.bareword_values || self.at_delimiter() => Ok(event),
+            _ => {
+                self.pos = start;
+                self.warnings.truncate(warnings);
+                let word = self.bareword();
+                self.warn(WarningKind::Bareword, start, self.pos);
+                Ok(Event::String(word))
+            }
+        }
+    }
+
+    /// An unquoted string value running up to the next `,`, bracket or line
+    /// break, without trailing whitespace.
+    fn bareword(&mut self) -> Cow<'a, str> {
+        let start = self.pos;
+        let mut end = self.pos;
+        while let Some(c) = self.peek_char() {
+            if is_structural(c) || c == '\n' || c == '\r' || self.at_comment() ||
+               (c == ')' && self.stack.last() == Some(&Frame::Tuple)) {
+                break;
+            }
+            self.pos += c.len_utf8();
+            if !c.is_whitespace() {
+                end = self.pos;
+            }
+        }
+        self.pos = end;
+        Cow::Borrowed(&self.text[start..end])
+    }
+
+    fn at_delimiter(&self) -> bool {
+        match self.peek_char() {
+            None => true,
+            Some(c) => {
+                c.is_whitespace() || c == '\u{feff}' || c == ',' || c == ']' || c == '}' ||
+                (c == ')' && self.stack.last() == Some(&Frame::Tuple)) || self.at_comment()
+            }
+        }
+    }
+
+    fn at_comment(&self) -> bool {
+        self.options.comments && self.peek() == Some(b'/') &&
+        (self.bytes.get(self.pos + 1) == Some(&b'/') || self.bytes.get(self.pos + 1) == Some(&b'*'))
+    }
+
+    fn number(&mut self) -> Result<Event<'a>, DecodeError> {
+        let start = self.pos;
+        let json5 = self.options.json5;
+        let mut negative = false;
+        match self.peek() {
+            Some(b'-') => {
+                negative = true;
+                self.pos += 1;
+            }
+            Some(b'+') if json5 => self.pos += 1,
+            _ => {}
+        }
+
+        if let Some(c) = self.peek_char() {
+            if is_ident_start(c) {
+                let word = self.identifier()?;
+                let f = match self.special_float(&word) {
+                    Some(f) => f,
+                    None => return Err(self.error(ErrorKind::InvalidNumber, start)),
+                };
+                self.warn(WarningKind::NonFiniteNumber, start, self.pos);
+                return Ok(Event::Float(if negative { -f } else { f }));
+            }
+        }
+        if json5 && self.peek() == Some(b'0') &&
+           (self.bytes.get(self.pos + 1) == Some(&b'x') ||
+            self.bytes.get(self.pos + 1) == Some(&b'X')) {
+            self.pos += 2;
+            let event = self.hex_number(start, negative)?;
+            self.check_number(start)?;
+            if self.options.raw_numbers {
+                return Ok(Event::Number(Cow::Borrowed(&self.text[start..self.pos])));
+            }
+            self.warn(WarningKind::NumberSyntax, start, self.pos);
+            return Ok(event);
+        }
+
+        let int_start = self.pos;
+        let int_digits = self.digits();
+        let mut float = false;
+        let mut frac_digits = 0;
+        if self.peek() == Some(b'.') {
+            float = true;
+            self.pos += 1;
+            frac_digits = self.digits();
+        }
+        if int_digits + frac_digits == 0 ||
+           (!json5 && (int_digits == 0 || (float && frac_digits == 0))) {
+            return Err(self.error(ErrorKind::InvalidNumber, start));
+        }
+        if self.peek() == Some(b'e') || self.peek() == Some(b'E') {
+            float = true;
+            self.pos += 1;
+            if self.peek() == Some(b'+') || self.peek() == Some(b'-') {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(self.error(ErrorKind::InvalidNumber, start));
+            }
+        }
+        if self.bytes[start] == b'+' || int_digits == 0 || (float && frac_digits == 0) ||
+           (int_digits > 1 && self.bytes[int_start] == b'0') {
+            let end = self.pos;
+            self.warn(WarningKind::NumberSyntax, start, end);
+        }
+
+        self.check_number(start)?;
+        let lexeme = &self.text[start..self.pos];
+        if self.options.raw_numbers {
+            return Ok(Event::Number(Cow::Borrowed(lexeme)));
+        }
+        if !float {
+            if let Ok(i) = i64::from_str(lexeme) {
+                return Ok(Event::Integer(i));
+            }
+        }
+        if self.options.decimals {
+            if let Some(d) = Decimal::from_lexeme(lexeme) {
+                return Ok(Event::Decimal(d));
+            }
+        }
+        match f64::from_str(lexeme) {
+            Ok(f) => Ok(Event::Float(f)),
+            Err(_) => Err(self.error(ErrorKind::InvalidNumber, start)),
+        }
+    }
+
+    fn check_number(&self, start: usize) -> Result<(), DecodeError> {
+        if self.options.limits.max_number_length.is_some_and(|max| self.pos - start > max) {
+            return Err(self.error(ErrorKind::LimitExceeded(Limit::NumberLength), start));
+        }
+        Ok(())
+    }
+
+    /// `Infinity`/`NaN` (JSON5, JavaScript) and `inf`/`nan` (Python).
+    fn special_float(&self, word: &str) -> Option<f64> {
+        let javascript = self.options.json5 || self.options.javascript_literals;
+        match word {
+            "Infinity" if javascript => Some(f64::INFINITY),
+            "NaN" if javascript => Some(f64::NAN),
+            "inf" if self.options.python_literals => Some(f64::INFINITY),
+            "nan" if self.options.python_literals => Some(f64::NAN),
+            _ => None,
+        }
+    }
+
+    fn hex_number(&mut self, start: usize, negative: bool) -> Result<Event<'a>, DecodeError> {
+        let digits_start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_hexdigit()) {
+            self.pos += 1;
+        }
+        let digits = &self.text[digits_start..self.pos];
+        if digits.is_empty() {
+            return Err(self.error(ErrorKind::InvalidNumber, start));
+        }
+        if let Ok(u) = u64::from_str_radix(digits, 16) {
+            if u <= i64::MAX as u64 {
+                let i = u as i64;
+                return Ok(Event::Integer(if negative { -i } else { i }));
+            }
+            if negative && u == 1 << 63 {
+                return Ok(Event::Integer(i64::MIN));
+            }
+        }
+        let f = digits.chars().fold(0.0, |acc, c| acc * 16.0 + c.to_digit(16).unwrap() as f64);
+        Ok(Event::Float(if negative { -f } else { f }))
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn string(&mut self, quote: u8) -> Result<Cow<'a, str>, DecodeError> {
+        let start = self.pos;
+        if let Some(end) = self.indexed_string(start) {
+            let content = &self.bytes[start + 1..end];
+            // Checked rather than trusted, for the result to be the byte
+            // loop's in any case.
+            if quote == b'"' && !self.report && self.bytes.get(end) == Some(&b'"') &&
+               !content.iter().any(|&b| b == b'\\' || b == b'"') {
+                self.pos = end + 1;
+                return Ok(Cow::Borrowed(&self.text[start + 1..end]));
+            }
+        }
+        self.pos += 1;
+        let content_start = self.pos;
+        loop {
+            match self.bytes.get(self.pos) {
+                None if self.repair => {
+                    self.log(RepairKind::ClosedString, start);
+                    return Ok(Cow::Borrowed(&self.text[content_start..]));
+                }
+                None => return Err(self.error(ErrorKind::UnterminatedString, start)),
+                Some(&b) if b == quote => {
+                    self.pos += 1;
+                    return Ok(Cow::Borrowed(&self.text[content_start..self.pos - 1]));
+                }
+                Some(&b'\\') => break,
+                Some(&b) => {
+                    if b < 0x20 {
+                        let pos = self.pos;
+                        self.warn(WarningKind::ControlCharacter, pos, pos + 1);
+                    }
+                    self.pos += 1;
+                }
+            }
+        }
+
+        let mut owned = self.text[content_start..self.pos].to_owned();
+        loop {
+            match self.bytes.get(self.pos) {
+                None if self.repair => {
+                    self.log(RepairKind::ClosedString, start);
+                    return Ok(Cow::Owned(owned));
+                }
+                None => return Err(self.error(ErrorKind::UnterminatedString, start)),
+                Some(&b) if b == quote => {
+                    self.pos += 1;
+                    return Ok(Cow::Owned(owned));
+                }
+                Some(&b'\\') => {
+                    match self.escape(&mut owned) {
+                        Err(ref e) if self.recover && e.kind == ErrorKind::InvalidEscape => {
+                            self.errors.push(e.clone());
+                            owned.push('\u{fffd}');
+                        }
+                        result => result?,
+                    }
+                }
+                Some(&b) => {
+                    if b < 0x20 {
+                        let pos = self.pos;
+                        self.warn(WarningKind::ControlCharacter, pos, pos + 1);
+                    }
+                    let c = self.peek_char().unwrap();
+                    owned.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    /// The closing quote of the string opening at `start`, if the index has
+    /// it.
+    fn indexed_string(&mut self, start: usize) -> Option<usize> {
+        while self.index.get(self.indexed).is_some_and(|&p| (p as usize) < start) {
+            self.indexed += 1;
+        }
+        if self.index.get(self.indexed) != Some(&(start as u32)) {
+            return None;
+        }
+        self.indexed += 2;
+        self.index.get(self.indexed - 1).map(|&end| end as usize)
+    }
+
+    fn escape(&mut self, out: &mut String) -> Result<(), DecodeError> {
+        let escape_at = self.pos;
+        self.pos += 1;
+        let c = match self.peek_char() {
+            Some(c) => c,
+            None if self.repair => return Ok(()),
+            None => return Err(self.error(ErrorKind::UnterminatedString, escape_at)),
+        };
+        self.pos += c.len_utf8();
+        if !matches!(c, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'u') {
+            let end = self.pos;
+            self.warn(WarningKind::Escape, escape_at, end);
+        }
+        match c {
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'u' => out.push(self.unicode_escape(escape_at)?),
+            'v' if self.options.json5 => out.push('\u{b}'),
+            '0' if self.options.json5 => out.push('\0'),
+            'x' if self.options.json5 || self.options.python_literals => {
+                let code = self.hex_digits(2, escape_at)?;
+                out.push(char::from_u32(code).unwrap());
+            }
+            '\r' if self.options.json5 => {
+                if self.peek() == Some(b'\n') {
+                    self.pos += 1;
+                }
+            }
+            '\n' | '\u{2028}' | '\u{2029}' if self.options.json5 => {}
+            c => out.push(c),
+        }
+        Ok(())
+    }
+
+    /// Reads the `XXXX` of a `\uXXXX` escape, joining surrogate pairs.
+    fn unicode_escape(&mut self, escape_at: usize) -> Result<char, DecodeError> {
+        let code = self.hex_digits(4, escape_at)?;
+        if (0xD800..0xDC00).contains(&code) && self.bytes.get(self.pos) == Some(&b'\\') &&
+           self.bytes.get(self.pos + 1) == Some(&b'u') {
+            let resume = self.pos;
+            self.pos += 2;
+            let low = self.hex_digits(4, resume)?;
+            if (0xDC00..0xE000).contains(&low) {
+                let c = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                return Ok(char::from_u32(c).unwrap());
+            }
+            self.pos = resume;
+        }
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => {
+                let end = self.pos;
+                self.warn(WarningKind::LoneSurrogate, escape_at, end);
+                Ok('\u{fffd}')
+            }
+        }
+    }
+
+    fn hex_digits(&mut self, count: usize, escape_at: usize) -> Result<u32, DecodeError> {
+        let digits = match self.text.get(self.pos..self.pos + count) {
+            Some(digits) => digits,
+            None => return Err(self.error(ErrorKind::InvalidEscape, escape_at)),
+        };
+        match u32::from_str_radix(digits, 16) {
+            Ok(code) if digits.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                self.pos += count;
+                Ok(code)
+            }
+            _ => Err(self.error(ErrorKind::InvalidEscape, escape_at)),
+        }
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), DecodeError> {
+        loop {
+            match self.peek() {
+                Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => self.pos += 1,
+                Some(b'/') if self.options.comments => {
+                    if !self.comment()? {
+                        return Ok(());
+                    }
+                }
+                Some(b) if b >= 0x80 || b == 0x0b || b == 0x0c => {
+                    let c = self.peek_char().unwrap();
+                    if c == '\u{feff}' || (self.options.json5 && is_json5_space(c)) {
+                        let (start, end) = (self.pos, self.pos + c.len_utf8());
+                        if c == '\u{feff}' {
+                            self.warn(WarningKind::ByteOrderMark, start, end);
+                        } else {
+                            self.warn(WarningKind::Whitespace, start, end);
+                        }
+                        self.pos = end;
+                    } else {
+                        return Ok(());
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn comment(&mut self) -> Result<bool, DecodeError> {
+        let start = self.pos;
+        match self.bytes.get(self.pos + 1) {
+            Some(&b'/') => {
+                while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' &&
+                      self.bytes[self.pos] != b'\r' {
+                    self.pos += 1;
+                }
+            }
+            Some(&b'*') => {
+                match self.text[self.pos + 2..].find("*/") {
+                    Some(i) => self.pos += i + 4,
+                    None if self.repair => {
+                        self.log(RepairKind::ClosedComment, start);
+                        self.pos = self.bytes.len();
+                    }
+                    None => return Err(self.error(ErrorKind::UnterminatedComment, start)),
+                }
+            }
+            _ => return Ok(false),
+        }
+        let end = self.pos;
+        self.warn(WarningKind::Comment, start, end);
+        if self.retain_comments {
+            self.comments.push((start, end));
+        }
+        Ok(true)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).cloned()
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn unexpected(&self) -> DecodeError {
+        self.unexpected_expecting(self.expected())
+    }
+
+    fn unexpected_expecting(&self, expected: Vec<&'static str>) -> DecodeError {
+        let kind = match self.peek_char() {
+            Some(c) => ErrorKind::UnexpectedCharacter(c),
+            None => ErrorKind::UnexpectedEndOfInput,
+        };
+        self.diagnose(kind, self.pos, expected)
+    }
+
+    fn error(&self, kind: ErrorKind, offset: usize) -> DecodeError {
+        let expected = match kind {
+            ErrorKind::UnexpectedCharacter(_) |
+            ErrorKind::UnexpectedEndOfInput |
+            ErrorKind::TrailingCharacters => self.expected(),
+            _ => Vec::new(),
+        };
+        self.diagnose(kind, offset, expected)
+    }
+
+    /// The tokens the current state accepts, for error messages.
+    fn expected(&self) -> Vec<&'static str> {
+        let close = match self.stack.last() {
+            Some(&Frame::Object) => "'}'",
+            Some(&Frame::Tuple) => "')'",
+            _ => "']'",
+        };
+        let trailing = self.options.trailing_commas;
+        match self.state {
+            State::Root | State::ObjectValue => vec!["value"],
+            State::ArrayFirst => vec!["value", close],
+            State::ArrayElement if trailing => vec!["value", close],
+            State::ArrayElement => vec!["value"],
+            State::ArrayNext | State::ObjectNext => vec!["','", close],
+            State::ObjectFirst => vec!["key", "'}'"],
+            State::ObjectKey if trailing => vec!["key", "'}'"],
+            State::ObjectKey => vec!["key"],
+            State::RootEnd => vec!["end of input"],
+            State::Repairing | State::Recovering | State::Done => Vec::new(),
+        }
+    }
+
+    fn diagnose(&self, kind: ErrorKind, offset: usize, expected: Vec<&'static str>) -> DecodeError {
+        let hint = self.hint(&kind, offset, &expected);
+        let mut error = DecodeError::at(kind, self.text, self.locate(offset));
+        error.expected = expected;
+        error.hint = hint;
+        error
+    }
+
+    fn hint(&self, kind: &ErrorKind, offset: usize, expected: &[&str]) -> Option<&'static str> {
+        let c = match *kind {
+            ErrorKind::UnexpectedCharacter(c) => c,
+            ErrorKind::UnexpectedEndOfInput if self.stack.is_empty() => {
+                return Some("the input is empty")
+            }
+            ErrorKind::UnexpectedEndOfInput => return Some("a bracket is never closed"),
+            ErrorKind::UnterminatedString => return Some("missing closing quote"),
+            ErrorKind::UnterminatedComment => return Some("missing \"*/\""),
+            ErrorKind::InvalidEscape => return Some("\\u and \\x need hexadecimal digits"),
+            ErrorKind::InvalidNumber |
+            ErrorKind::InvalidUtf8 |
+            ErrorKind::DuplicateKey(_) |
+            ErrorKind::LimitExceeded(_) |
+            ErrorKind::Io(_) |
+            ErrorKind::Rejected(_) => return None,
+            ErrorKind::TrailingCharacters => return Some("only one top-level value is allowed"),
+        };
+        let expects = |token| expected.contains(&token);
+        let in_object = self.stack.last() == Some(&Frame::Object);
+
+        if c == '/' && !self.options.comments {
+            Some("comments are not enabled")
+        } else if c == '\'' && !self.options.single_quotes {
+            Some("strings must be double-quoted")
+        } else if expects("':'") {
+            Some("missing colon after key")
+        } else if expects("','") {
+            if c == ']' || c == '}' || c == ')' {
+                Some("closing bracket does not match the opening one")
+            } else if c != '"' && offset > 0 && self.bytes[offset - 1] == b'"' {
+                Some("unescaped quote inside string")
+            } else if in_object {
+                Some("missing comma between object members")
+            } else {
+                Some("missing comma between array elements")
+            }
+        } else if self.state == State::ObjectValue && (c == ',' || c == '}') {
+            Some("missing value after key")
+        } else if matches!(self.state, State::ArrayElement | State::ObjectKey) &&
+                  (c == ']' || c == '}' || c == ')') {
+            Some("trailing comma before closing bracket")
+        } else if self.state == State::ArrayElement && c == ',' {
+            Some("empty array element or doubled comma")
+        } else if expects("key") && is_ident_part(c) {
+            Some("object keys must be double-quoted")
+        } else if expects("value") && is_ident_start(c) {
+            Some("strings must be quoted; literals are true, false and null")
+        } else {
+            None
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c == '$' || c == '_' || c.is_alphabetic()
+}
+
+fn is_ident_part(c: char) -> bool {
+    is_ident_start(c) || c.is_alphanumeric() || c == '\u{200c}' || c == '\u{200d}'
+}
+
+fn closing_bracket(frame: Frame) -> u8 {
+    match frame {
+        Frame::Array => b']',
+        Frame::Tuple => b')',
+        Frame::Object => b'}',
+    }
+}
+
+fn is_structural(c: char) -> bool {
+    matches!(c, ',' | '[' | ']' | '{' | '}')
+}
+
+fn is_json5_space(c: char) -> bool {
+    matches!(c,
+             '\u{b}' | '\u{c}' | '\u{a0}' | '\u{1680}' | '\u{2000}'..='\u{200a}' | '\u{2028}' |
+             '\u{2029}' | '\u{202f}' | '\u{205f}' | '\u{3000}' | '\u{feff}')
+}
+
+/// Events until the end of input or the first error.
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<Event<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Result<Event<'a>, DecodeError>> {
+        let result = self.next_event();
+        if result.is_err() {
+            self.state = State::Done;
+        }
+        result.transpose()
+    }
+}
+
+pub fn decode_with(text: String, options: &Options) -> Result<Box<JFObject>, DecodeError> {
+    let mut parser = Parser::new(&text, options.clone());
+    parser.index_strings();
+    let mut builder = JFObjectBuilder::new(options);
+    visit(&mut parser, &mut builder)?;
+    Ok(Box::new(builder.finish()))
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+use json_flex::JFObject;
+
+/// One step from a container to one of its children.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PathSegment::Key(ref k) => write!(f, "[{:?}]", k),
+            PathSegment::Index(i) => write!(f, "[{}]", i),
+        }
+    }
+}
+
+impl JFObject {
+    /// Follows `path` from this value; `None` if any step is missing.
+    pub fn get_path(&self, path: &[PathSegment]) -> Option<&JFObject> {
+        let mut value = self;
+        for segment in path {
+            value = match (value, segment) {
+                (JFObject::Array(v), PathSegment::Index(i)) => v.get(*i)?,
+                (JFObject::Dictionary(v), PathSegment::Key(k)) => v.get(k)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
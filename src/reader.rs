@@ -0,0 +1,233 @@
+use std::borrow::Cow;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::str;
+
+use error::{DecodeError, ErrorKind, Position, CONTEXT};
+use limits::Limit;
+use options::Options;
+use parser::{Event, Parser};
+
+/// Bytes asked of the reader at a time.
+const CHUNK: usize = 8192;
+
+/// How far the input must reach past where the parser stopped for an error
+/// to stand, rather than be the input being cut short.
+const MARGIN: usize = 16;
+
+/// A `Parser` reading its input from `reader` as it goes, for documents too
+/// large to hold in memory. Only the input from the last event on is kept,
+/// with enough of the line before it for errors to show. The strings and keys
+/// of an event borrow that buffer, so each event must be done with before the
+/// next is asked for.
+pub struct ReaderParser<R> {
+    reader: R,
+    /// The input not yet parsed past, as far as it is valid UTF-8.
+    buffer: String,
+    /// Bytes read after `buffer` that do not make a whole character yet.
+    pending: Vec<u8>,
+    /// Where `buffer` starts in the input.
+    base: Position,
+    eof: bool,
+    /// The parser, between events, with offsets into `buffer`.
+    parser: Parser<'static>,
+    position: Position,
+}
+
+impl<R: Read> ReaderParser<R> {
+    pub fn new(reader: R, options: Options) -> ReaderParser<R> {
+        let base = Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        };
+        ReaderParser {
+            reader,
+            buffer: String::new(),
+            pending: Vec::new(),
+            base,
+            eof: false,
+            parser: Parser::new("", options),
+            position: base,
+        }
+    }
+
+    /// The next event, reading as much as it takes to be sure of it.
+    pub fn next_event(&mut self) -> Result<Option<Event<'_>>, DecodeError> {
+        self.compact();
+        let (event, borrowed) = loop {
+            let mut parser = self.parser.clone().moved(&self.buffer, 0, self.origin());
+            let result = parser.next_event();
+            let stop = parser.offset();
+            let complete = self.eof ||
+                           match result {
+                               // Only something other than space after it
+                               // shows that a number or bareword has ended.
+                               Ok(Some(_)) => !self.buffer[stop..].trim_start().is_empty(),
+                               Ok(None) => false,
+                               Err(ref e) => {
+                                   self.buffer.len() >= stop.max(e.position.offset) + MARGIN
+                               }
+                           };
+            if complete {
+                let result = result.map(|event| event.map(|e| detach(e, &self.buffer)));
+                let position = parser.position();
+                self.parser = parser.moved("", 0, self.origin());
+                self.position = Position {
+                    offset: self.base.offset + position.offset,
+                    ..position
+                };
+                match result {
+                    Ok(Some(event)) => break event,
+                    Ok(None) => return Ok(None),
+                    Err(mut e) => {
+                        e.position.offset += self.base.offset;
+                        return Err(e);
+                    }
+                }
+            }
+            self.fill()?;
+        };
+        Ok(Some(match borrowed {
+            Some(range) => attach(event, &self.buffer[range]),
+            None => event,
+        }))
+    }
+
+    /// Source range of the last event in the input, as `Parser::token`.
+    pub fn token(&self) -> Range<usize> {
+        let token = self.parser.token();
+        token.start + self.base.offset..token.end + self.base.offset
+    }
+
+    /// Line and column of the start of `token()`.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Where `buffer` starts, for the parser, which counts offsets from there.
+    fn origin(&self) -> Position {
+        Position {
+            offset: 0,
+            ..self.base
+        }
+    }
+
+    /// Drops the input the parser is past once that is at least half of it,
+    /// so that the buffer holds little more than the value being parsed.
+    fn compact(&mut self) {
+        let consumed = self.parser.offset();
+        if consumed * 2 < self.buffer.len() {
+            return;
+        }
+        // What `DecodeError::source_line` shows before an error.
+        let line = self.buffer[..consumed].rfind('\n').map_or(0, |i| i + 1);
+        let consumed = match self.buffer[line..consumed].char_indices().rev().nth(CONTEXT) {
+            Some((i, _)) => line + i,
+            None => line,
+        };
+        if consumed == 0 {
+            return;
+        }
+        for c in self.buffer[..consumed].chars() {
+            if c == '\n' {
+                self.base.line += 1;
+                self.base.column = 1;
+            } else {
+                self.base.column += 1;
+            }
+        }
+        self.base.offset += consumed;
+        self.buffer.drain(..consumed);
+        let parser = self.parser.clone();
+        self.parser = parser.moved("", consumed, self.origin());
+    }
+
+    /// Reads the next chunk into `buffer`, or sets `eof`.
+    fn fill(&mut self) -> Result<(), DecodeError> {
+        let mut chunk = [0; CHUNK];
+        let n = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(n) => break n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.error(ErrorKind::Io(e.kind()))),
+            }
+        };
+        if n == 0 {
+            self.eof = true;
+            if !self.pending.is_empty() {
+                return Err(self.error(ErrorKind::InvalidUtf8));
+            }
+            return Ok(());
+        }
+        let read = self.base.offset + self.buffer.len() + self.pending.len() + n;
+        if self.parser.options().limits.max_input_bytes.is_some_and(|max| read > max) {
+            return Err(self.error(ErrorKind::LimitExceeded(Limit::InputBytes)));
+        }
+        self.pending.extend_from_slice(&chunk[..n]);
+        let (valid, invalid) = match str::from_utf8(&self.pending) {
+            Ok(_) => (self.pending.len(), false),
+            Err(e) => (e.valid_up_to(), e.error_len().is_some()),
+        };
+        if let Ok(text) = str::from_utf8(&self.pending[..valid]) {
+            self.buffer.push_str(text);
+        }
+        self.pending.drain(..valid);
+        if invalid {
+            return Err(self.error(ErrorKind::InvalidUtf8));
+        }
+        Ok(())
+    }
+
+    /// An error at the end of the input read so far.
+    fn error(&self, kind: ErrorKind) -> DecodeError {
+        let mut position = self.origin();
+        for c in self.buffer.chars() {
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+        position.offset = self.buffer.len();
+        let mut error = DecodeError::at(kind, &self.buffer, position);
+        error.position.offset += self.base.offset;
+        error
+    }
+}
+
+/// `event` without its borrow of `text`, and the range of `text` it
+/// borrowed, for `attach` to restore.
+fn detach(event: Event, text: &str) -> (Event<'static>, Option<Range<usize>>) {
+    let range = |s: &str| {
+        let start = s.as_ptr() as usize - text.as_ptr() as usize;
+        Some(start..start + s.len())
+    };
+    match event {
+        Event::Key(Cow::Borrowed(s)) => (Event::Key(Cow::Borrowed("")), range(s)),
+        Event::String(Cow::Borrowed(s)) => (Event::String(Cow::Borrowed("")), range(s)),
+        Event::Number(Cow::Borrowed(s)) => (Event::Number(Cow::Borrowed("")), range(s)),
+        Event::Key(Cow::Owned(s)) => (Event::Key(Cow::Owned(s)), None),
+        Event::String(Cow::Owned(s)) => (Event::String(Cow::Owned(s)), None),
+        Event::Number(Cow::Owned(s)) => (Event::Number(Cow::Owned(s)), None),
+        Event::StartObject => (Event::StartObject, None),
+        Event::EndObject => (Event::EndObject, None),
+        Event::StartArray => (Event::StartArray, None),
+        Event::EndArray => (Event::EndArray, None),
+        Event::Integer(i) => (Event::Integer(i), None),
+        Event::Float(f) => (Event::Float(f), None),
+        Event::Decimal(d) => (Event::Decimal(d), None),
+        Event::Bool(b) => (Event::Bool(b), None),
+        Event::Null => (Event::Null, None),
+    }
+}
+
+/// `event` borrowing `s` again.
+fn attach<'a>(event: Event<'static>, s: &'a str) -> Event<'a> {
+    match event {
+        Event::Key(_) => Event::Key(Cow::Borrowed(s)),
+        Event::Number(_) => Event::Number(Cow::Borrowed(s)),
+        _ => Event::String(Cow::Borrowed(s)),
+    }
+}
@@ -0,0 +1,26 @@
+use error::DecodeError;
+use json_flex::JFObject;
+use options::Options;
+use parser::Parser;
+use visitor::build;
+
+/// Decodes as much as possible, returning every syntax error instead of
+/// stopping at the first. After an error, parsing resumes at the next `,`,
+/// `]` or `}`; the element or member that failed is left out of the value.
+pub fn recover(text: &str) -> (JFObject, Vec<DecodeError>) {
+    recover_with(text, &Options::default())
+}
+
+pub fn recover_with(text: &str, options: &Options) -> (JFObject, Vec<DecodeError>) {
+    let mut parser = Parser::new(text, options.clone());
+    parser.recover_mode();
+    match build(&mut parser, |_, _| {}) {
+        Ok(value) => (value, parser.take_errors()),
+        // Exceeding a limit stops decoding.
+        Err(e) => {
+            let mut errors = parser.take_errors();
+            errors.push(e);
+            (JFObject::Null, errors)
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use error::Position;
+use json_flex::JFObject;
+use options::Options;
+use parser::Parser;
+use visitor::build;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairKind {
+    /// A string ran to the end of the input; it was closed there.
+    ClosedString,
+    /// A block comment ran to the end of the input.
+    ClosedComment,
+    /// An array or tuple was missing its closing bracket.
+    ClosedArray,
+    /// An object was missing its closing brace.
+    ClosedObject,
+    /// A key without a value was left out of its object.
+    DroppedKey,
+    /// Two values were not separated by a comma.
+    InsertedComma,
+    /// A key and its value were not separated by a colon.
+    InsertedColon,
+    /// `tru`, `fals`, `nul` and the like at the end of the input.
+    CompletedLiteral,
+    /// Input that could not be parsed, up to the end or a single stray
+    /// bracket, was skipped.
+    DroppedInput,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repair {
+    pub kind: RepairKind,
+    pub position: Position,
+}
+
+/// Decodes truncated or malformed JSON as far as it makes sense, returning
+/// the value together with what had to be changed to get it.
+pub fn repair(text: &str) -> (JFObject, Vec<Repair>) {
+    let options = Options {
+        trailing_commas: true,
+        comments: true,
+        ..Options::default()
+    };
+    repair_with(text, &options)
+}
+
+pub fn repair_with(text: &str, options: &Options) -> (JFObject, Vec<Repair>) {
+    let mut parser = Parser::new(text, options.clone());
+    parser.repair_mode();
+    let value = build(&mut parser, |_, _| {}).unwrap_or(JFObject::Null);
+    (value, parser.take_repairs())
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+
+use error::{DecodeError, Position};
+use json_flex::JFObject;
+use options::Options;
+use parser::Parser;
+use visitor::build;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A byte order mark, at the start or between tokens.
+    ByteOrderMark,
+    /// Vertical tab, form feed or a Unicode space separator between tokens.
+    Whitespace,
+    /// A `//` or `/* */` comment.
+    Comment,
+    /// A `'single quoted'` string or key.
+    SingleQuotes,
+    /// An identifier used as a key.
+    UnquotedKey,
+    /// An unquoted word decoded as a string.
+    Bareword,
+    /// A comma directly before `]`, `)` or `}`.
+    TrailingComma,
+    /// An empty array slot, decoded as `null` under `ArrayHoles::Null` and
+    /// left out under `ArrayHoles::Skip`.
+    ArrayHole,
+    /// A `( )` tuple decoded as an array.
+    Tuple,
+    /// Hexadecimal, a leading `+` or zero, or a leading or trailing decimal
+    /// point.
+    NumberSyntax,
+    /// `Infinity`, `NaN`, `inf` or `nan`.
+    NonFiniteNumber,
+    /// `True`, `False`, `None` or `undefined`.
+    Literal,
+    /// An escape other than `\" \\ \/ \b \f \n \r \t \uXXXX`.
+    Escape,
+    /// A `\u` escape naming half of a surrogate pair, decoded as U+FFFD.
+    LoneSurrogate,
+    /// A raw control character inside a string.
+    ControlCharacter,
+    /// A key that already appeared in the same object.
+    DuplicateKey,
+}
+
+/// A non-standard construct that was accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub position: Position,
+    /// The source text of the construct, cut after 40 characters.
+    pub snippet: String,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            WarningKind::ByteOrderMark => "byte order mark",
+            WarningKind::Whitespace => "non-standard whitespace",
+            WarningKind::Comment => "comment",
+            WarningKind::SingleQuotes => "single-quoted string",
+            WarningKind::UnquotedKey => "unquoted key",
+            WarningKind::Bareword => "unquoted string value",
+            WarningKind::TrailingComma => "trailing comma",
+            WarningKind::ArrayHole => "empty array element",
+            WarningKind::Tuple => "tuple",
+            WarningKind::NumberSyntax => "non-standard number",
+            WarningKind::NonFiniteNumber => "non-finite number",
+            WarningKind::Literal => "non-standard literal",
+            WarningKind::Escape => "non-standard escape sequence",
+            WarningKind::LoneSurrogate => "lone surrogate escape",
+            WarningKind::ControlCharacter => "unescaped control character",
+            WarningKind::DuplicateKey => "duplicate key",
+        };
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{} at line {} column {}: {:?}",
+               self.kind,
+               self.position.line,
+               self.position.column,
+               self.snippet)
+    }
+}
+
+/// Like `decode_with`, additionally listing every construct that strict JSON
+/// would have rejected, in input order.
+pub fn decode_with_report(text: String,
+                          options: &Options)
+                          -> Result<(Box<JFObject>, Vec<Warning>), DecodeError> {
+    let mut parser = Parser::new(&text, options.clone());
+    parser.report_warnings();
+    let value = build(&mut parser, |_, _| {})?;
+    Ok((Box::new(value), parser.take_warnings()))
+}
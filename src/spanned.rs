@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+
+use error::{DecodeError, ErrorKind, Position};
+use json_flex::JFObject;
+use number::{Decimal, Number};
+use options::{DuplicateKeys, Options};
+use parser::{Event, Parser};
+use path::PathSegment;
+
+/// Where a node sits in the input; `end` is just past its last character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+/// `JFObject` with a span on every value and key. Object members stay in
+/// input order, with repeated keys handled as `Options::duplicate_keys` says.
+/// A collected array spans from the first value to the last.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedValue {
+    Integer(i64),
+    Float(f64),
+    Number(Number),
+    Decimal(Decimal),
+    String(String),
+    Dictionary(Vec<(Spanned<String>, Spanned<SpannedValue>)>),
+    Array(Vec<Spanned<SpannedValue>>),
+    Null,
+    False,
+    True,
+}
+
+impl Spanned<SpannedValue> {
+    /// The member for `key`.
+    pub fn get(&self, key: &str) -> Option<&Spanned<SpannedValue>> {
+        match self.value {
+            SpannedValue::Dictionary(ref members) => {
+                members.iter().rev().find(|m| m.0.value == key).map(|m| &m.1)
+            }
+            _ => None,
+        }
+    }
+
+    /// The key of the member for `key`, for pointing at the key itself.
+    pub fn get_key(&self, key: &str) -> Option<&Spanned<String>> {
+        match self.value {
+            SpannedValue::Dictionary(ref members) => {
+                members.iter().rev().find(|m| m.0.value == key).map(|m| &m.0)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_path(&self, path: &[PathSegment]) -> Option<&Spanned<SpannedValue>> {
+        let mut node = self;
+        for segment in path {
+            node = match (&node.value, segment) {
+                (SpannedValue::Array(v), PathSegment::Index(i)) => v.get(*i)?,
+                (SpannedValue::Dictionary(_), PathSegment::Key(k)) => node.get(k)?,
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+
+    /// The value without spans, as `decode_with` would return it.
+    pub fn to_jfobject(&self) -> JFObject {
+        match self.value {
+            SpannedValue::Integer(i) => JFObject::Integer(i),
+            SpannedValue::Float(f) => JFObject::Float(f),
+            SpannedValue::Number(ref n) => JFObject::Number(n.clone()),
+            SpannedValue::Decimal(d) => JFObject::Decimal(d),
+            SpannedValue::String(ref s) => JFObject::String(s.clone()),
+            SpannedValue::Dictionary(ref members) => {
+                let mut map = HashMap::new();
+                for (k, v) in members {
+                    map.insert(k.value.clone(), v.to_jfobject());
+                }
+                JFObject::Dictionary(map)
+            }
+            SpannedValue::Array(ref v) => {
+                JFObject::Array(v.iter().map(|v| v.to_jfobject()).collect())
+            }
+            SpannedValue::Null => JFObject::Null,
+            SpannedValue::False => JFObject::False,
+            SpannedValue::True => JFObject::True,
+        }
+    }
+}
+
+/// Turns offsets into positions, walking forward from the previous one so
+/// that locating every node stays linear in the input size.
+struct Locator<'a> {
+    text: &'a str,
+    last: Position,
+}
+
+impl<'a> Locator<'a> {
+    fn position(&mut self, offset: usize) -> Position {
+        if offset < self.last.offset {
+            return Position::from_offset(self.text, offset);
+        }
+        let mut p = self.last;
+        for c in self.text[p.offset..offset].chars() {
+            if c == '\n' {
+                p.line += 1;
+                p.column = 1;
+            } else {
+                p.column += 1;
+            }
+        }
+        p.offset = offset;
+        self.last = p;
+        p
+    }
+
+    fn span(&mut self, start: usize, end: usize) -> Span {
+        Span {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+}
+
+struct Open {
+    start: Position,
+    value: SpannedValue,
+    key: Option<Spanned<String>>,
+    /// Where the member for each key is.
+    index: HashMap<String, usize>,
+    /// Members a later one with the same key replaces under
+    /// `DuplicateKeys::LastWins`, dropped when the object closes.
+    replaced: Vec<usize>,
+    /// Members `DuplicateKeys::Collect` has turned into arrays.
+    collected: Vec<usize>,
+}
+
+impl Open {
+    fn add(&mut self,
+           key: Spanned<String>,
+           value: Spanned<SpannedValue>,
+           duplicate_keys: DuplicateKeys) {
+        let members = match self.value {
+            SpannedValue::Dictionary(ref mut members) => members,
+            _ => unreachable!(),
+        };
+        let i = match self.index.get(&key.value) {
+            Some(&i) => i,
+            None => {
+                self.index.insert(key.value.clone(), members.len());
+                members.push((key, value));
+                return;
+            }
+        };
+        match duplicate_keys {
+            DuplicateKeys::LastWins => {
+                self.replaced.push(i);
+                self.index.insert(key.value.clone(), members.len());
+                members.push((key, value));
+            }
+            DuplicateKeys::FirstWins | DuplicateKeys::Error => {}
+            DuplicateKeys::Collect => {
+                let first = &mut members[i].1;
+                if !self.collected.contains(&i) {
+                    let span = first.span;
+                    let v = mem::replace(&mut first.value, SpannedValue::Array(Vec::new()));
+                    first.value = SpannedValue::Array(vec![Spanned { value: v, span }]);
+                    self.collected.push(i);
+                }
+                first.span.end = value.span.end;
+                if let SpannedValue::Array(ref mut v) = first.value {
+                    v.push(value);
+                }
+            }
+        }
+    }
+
+    fn close(mut self) -> SpannedValue {
+        if let SpannedValue::Dictionary(ref mut members) = self.value {
+            if !self.replaced.is_empty() {
+                let replaced = &mut self.replaced;
+                replaced.sort_unstable();
+                let mut i = 0;
+                members.retain(|_| {
+                    i += 1;
+                    replaced.binary_search(&(i - 1)).is_err()
+                });
+            }
+        }
+        self.value
+    }
+}
+
+/// Like `decode_with`, recording the span of every value and key.
+pub fn decode_spanned(text: String,
+                      options: &Options)
+                      -> Result<Spanned<SpannedValue>, DecodeError> {
+    let mut parser = Parser::new(&text, options.clone());
+    let mut locator = Locator {
+        text: &text,
+        last: Position::from_offset(&text, 0),
+    };
+    let mut root = None;
+    let mut stack: Vec<Open> = Vec::new();
+    let mut key: Option<Spanned<String>> = None;
+
+    while let Some(event) = parser.next_event()? {
+        let token = parser.token();
+        let value = match event {
+            Event::StartArray | Event::StartObject => {
+                stack.push(Open {
+                    start: locator.position(token.start),
+                    value: if event == Event::StartArray {
+                        SpannedValue::Array(Vec::new())
+                    } else {
+                        SpannedValue::Dictionary(Vec::new())
+                    },
+                    key: key.take(),
+                    index: HashMap::new(),
+                    replaced: Vec::new(),
+                    collected: Vec::new(),
+                });
+                continue;
+            }
+            Event::Key(k) => {
+                // Refused at the key, as `decode_with` does.
+                if stack.last().is_some_and(|open| open.index.contains_key(k.as_ref())) {
+                    parser.refuse(ErrorKind::DuplicateKey(k.to_string()))?;
+                }
+                key = Some(Spanned {
+                    value: k.into_owned(),
+                    span: locator.span(token.start, token.end),
+                });
+                continue;
+            }
+            Event::EndArray | Event::EndObject => {
+                let mut open = stack.pop().unwrap();
+                key = open.key.take();
+                Spanned {
+                    span: Span {
+                        start: open.start,
+                        end: locator.position(token.end),
+                    },
+                    value: open.close(),
+                }
+            }
+            Event::String(s) => SpannedValue::String(s.into_owned()).at(&mut locator, token),
+            Event::Integer(i) => SpannedValue::Integer(i).at(&mut locator, token),
+            Event::Float(f) => SpannedValue::Float(f).at(&mut locator, token),
+            Event::Number(n) => {
+                SpannedValue::Number(Number::from_lexeme(&n)).at(&mut locator, token)
+            }
+            Event::Decimal(d) => SpannedValue::Decimal(d).at(&mut locator, token),
+            Event::Bool(true) => SpannedValue::True.at(&mut locator, token),
+            Event::Bool(false) => SpannedValue::False.at(&mut locator, token),
+            Event::Null => SpannedValue::Null.at(&mut locator, token),
+        };
+
+        match stack.last_mut() {
+            Some(Open { value: SpannedValue::Array(v), .. }) => v.push(value),
+            Some(open) => open.add(key.take().unwrap(), value, options.duplicate_keys),
+            None => root = Some(value),
+        }
+    }
+
+    Ok(root.unwrap_or_else(|| SpannedValue::Null.at(&mut locator, 0..0)))
+}
+
+impl SpannedValue {
+    fn at(self, locator: &mut Locator, token: Range<usize>) -> Spanned<SpannedValue> {
+        Spanned {
+            value: self,
+            span: locator.span(token.start, token.end),
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use std::convert::TryInto;
+
+use error::{DecodeError, ErrorKind};
+use limits::Limit;
+use options::Options;
+
+/// How `structural_index_with` classifies the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanBackend {
+    /// One byte at a time.
+    Scalar,
+    /// 64-byte blocks classified into bitmasks in plain Rust.
+    Portable,
+    /// 64-byte blocks classified with SSE2, on x86_64.
+    Sse2,
+    /// 64-byte blocks classified with AVX2, on x86_64 CPUs that have it.
+    Avx2,
+}
+
+impl ScanBackend {
+    /// The fastest backend this CPU supports. Off x86_64 that is `Scalar`,
+    /// which beats `Portable` without vector instructions to classify with.
+    pub fn detect() -> ScanBackend {
+        if ScanBackend::Avx2.is_available() {
+            ScanBackend::Avx2
+        } else if ScanBackend::Sse2.is_available() {
+            ScanBackend::Sse2
+        } else {
+            ScanBackend::Scalar
+        }
+    }
+
+    pub fn is_available(self) -> bool {
+        match self {
+            ScanBackend::Scalar | ScanBackend::Portable => true,
+            #[cfg(target_arch = "x86_64")]
+            ScanBackend::Sse2 => true,
+            #[cfg(target_arch = "x86_64")]
+            ScanBackend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+}
+
+/// Offsets of the brackets, commas and colons in `text` that are outside
+/// strings and comments, in order. Parentheses count as brackets with
+/// `Options::python_literals`. A backslash hides the next character, also
+/// outside strings. With `Options::bareword_values` a bareword value is read
+/// as `decode_with` reads it, quotes and all, up to the next comma, bracket,
+/// line break or comment. Uses the fastest backend available.
+pub fn structural_index(text: &str, options: &Options) -> Result<Vec<u32>, DecodeError> {
+    structural_index_with(text, options, ScanBackend::detect())
+}
+
+/// `structural_index` with a chosen backend; one that is not available on
+/// this CPU, or any with `Options::bareword_values`, is replaced by `Scalar`.
+/// Every backend gives the same result.
+pub fn structural_index_with(text: &str,
+                             options: &Options,
+                             backend: ScanBackend)
+                             -> Result<Vec<u32>, DecodeError> {
+    scan(text, options, backend, false)
+}
+
+/// `structural_index` with the opening and closing quotes of the
+/// double-quoted strings too, for the parser to take strings whole.
+pub(crate) fn string_index(text: &str, options: &Options) -> Result<Vec<u32>, DecodeError> {
+    scan(text, options, ScanBackend::detect(), true)
+}
+
+fn scan(text: &str,
+        options: &Options,
+        backend: ScanBackend,
+        quotes: bool)
+        -> Result<Vec<u32>, DecodeError> {
+    if text.len() > u32::MAX as usize {
+        return Err(DecodeError::new(ErrorKind::LimitExceeded(Limit::InputBytes), text, 0));
+    }
+    let classify: fn(&[u8; 64], &Classes) -> Masks = match backend {
+        _ if options.bareword_values => return scan_scalar(text, options, quotes),
+        ScanBackend::Portable => classify_portable,
+        #[cfg(target_arch = "x86_64")]
+        ScanBackend::Sse2 => x86::classify_sse2,
+        #[cfg(target_arch = "x86_64")]
+        ScanBackend::Avx2 if backend.is_available() => x86::classify_avx2,
+        _ => return scan_scalar(text, options, quotes),
+    };
+    scan_blocks(text, options, quotes, classify)
+}
+
+fn scan_scalar(text: &str, options: &Options, quotes: bool) -> Result<Vec<u32>, DecodeError> {
+    let bytes = text.as_bytes();
+    let mut positions = Vec::new();
+    // Closing brackets of the open containers, and whether the innermost is
+    // an object expecting a key, for telling bareword values from keys.
+    let mut open = Vec::new();
+    let mut key = false;
+    let mut word = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        if word {
+            word = match bytes[i] {
+                b',' | b'[' | b']' | b'{' | b'}' | b'\n' | b'\r' => false,
+                b')' => open.last() != Some(&b')'),
+                b'/' if options.comments => !matches!(bytes.get(i + 1), Some(b'/') | Some(b'*')),
+                _ => true,
+            };
+            if word {
+                i += 1;
+                continue;
+            }
+        }
+        match bytes[i] {
+            b @ b'{' | b @ b'[' => {
+                positions.push(i as u32);
+                open.push(if b == b'{' { b'}' } else { b']' });
+                key = b == b'{';
+            }
+            b'(' if options.python_literals => {
+                positions.push(i as u32);
+                open.push(b')');
+                key = false;
+            }
+            b'}' | b']' => {
+                positions.push(i as u32);
+                open.pop();
+                key = false;
+            }
+            b')' if options.python_literals => {
+                positions.push(i as u32);
+                open.pop();
+                key = false;
+            }
+            b',' => {
+                positions.push(i as u32);
+                key = open.last() == Some(&b'}');
+            }
+            b':' => {
+                positions.push(i as u32);
+                key = false;
+            }
+            b'\\' => i += 1,
+            b'"' => {
+                let end = string_end(text, i)?;
+                if quotes {
+                    positions.push(i as u32);
+                    positions.push(end as u32);
+                }
+                i = end;
+            }
+            b'\'' if options.single_quotes => i = string_end(text, i)?,
+            b'/' if options.comments && bytes.get(i + 1) == Some(&b'/') => {
+                i = text[i..].find(['\n', '\r']).map_or(bytes.len(), |n| i + n);
+            }
+            b'/' if options.comments && bytes.get(i + 1) == Some(&b'*') => {
+                match text[i + 2..].find("*/") {
+                    Some(n) => i += n + 3,
+                    None => return Err(DecodeError::new(ErrorKind::UnterminatedComment, text, i)),
+                }
+            }
+            b' ' | b'\t' | b'\n' | b'\r' => {}
+            _ => word = options.bareword_values && !key,
+        }
+        i += 1;
+    }
+    Ok(positions)
+}
+
+/// Index of the closing quote of the string opened at `start`.
+fn string_end(text: &str, start: usize) -> Result<usize, DecodeError> {
+    let bytes = text.as_bytes();
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return Ok(i),
+            _ => i += 1,
+        }
+    }
+    Err(DecodeError::new(ErrorKind::UnterminatedString, text, start))
+}
+
+/// What the block scanner looks for.
+struct Classes {
+    parens: bool,
+    single_quotes: bool,
+    comments: bool,
+}
+
+/// One bit per byte of a block.
+#[derive(Default)]
+struct Masks {
+    quote: u64,
+    backslash: u64,
+    structural: u64,
+    /// `'` and `/` when they are enabled, which the bitmask path leaves to
+    /// the byte loop unless they are inside a double-quoted string.
+    special: u64,
+}
+
+/// Where the scan is at a block boundary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Carry {
+    Normal,
+    /// Outside strings, just after a backslash.
+    Escaped,
+    /// Inside a string with this quote; `true` if the next byte is escaped.
+    String(u8, bool),
+    /// Just after a `/` that may start a comment.
+    Slash,
+    LineComment,
+    /// Inside `/* */`; `true` just after a `*`.
+    BlockComment(bool),
+}
+
+const EVEN_BITS: u64 = 0x5555_5555_5555_5555;
+
+/// Finds quotes and structural characters 64 bytes at a time: backslash runs
+/// give the escaped bytes, a prefix XOR of the unescaped quotes gives the
+/// bytes inside strings, and what remains are the structural characters.
+/// Blocks with single quotes or comments outside strings go through the
+/// byte loop instead.
+fn scan_blocks(text: &str,
+               options: &Options,
+               quotes: bool,
+               classify: fn(&[u8; 64], &Classes) -> Masks)
+               -> Result<Vec<u32>, DecodeError> {
+    let classes = Classes {
+        parens: options.python_literals,
+        single_quotes: options.single_quotes,
+        comments: options.comments,
+    };
+    let bytes = text.as_bytes();
+    let mut positions = Vec::with_capacity(bytes.len() / 8);
+    let mut carry = Carry::Normal;
+    let mut padded = [b' '; 64];
+
+    for (n, chunk) in bytes.chunks(64).enumerate() {
+        let base = n * 64;
+        let block: &[u8; 64] = match chunk.try_into() {
+            Ok(block) => block,
+            Err(_) => {
+                padded[..chunk.len()].copy_from_slice(chunk);
+                &padded
+            }
+        };
+
+        let in_double = match carry {
+            Carry::Normal => Some((false, false)),
+            Carry::Escaped => Some((false, true)),
+            Carry::String(b'"', escaped) => Some((true, escaped)),
+            _ => None,
+        };
+        if let Some((in_string, escaped)) = in_double {
+            let masks = classify(block, &classes);
+            let mut next_escaped = escaped as u64;
+            let escaped = escaped_bits(masks.backslash, &mut next_escaped);
+            let carried = if in_string { !0 } else { 0 };
+            let strings = prefix_xor(masks.quote & !escaped) ^ carried;
+            if masks.special & !strings == 0 {
+                let mut bits = masks.structural & !strings & !escaped;
+                if quotes {
+                    // Here every quote that is not escaped opens or closes
+                    // a string.
+                    bits |= masks.quote & !escaped;
+                }
+                while bits != 0 {
+                    positions.push((base + bits.trailing_zeros() as usize) as u32);
+                    bits &= bits - 1;
+                }
+                carry = match (strings >> 63 == 1, next_escaped == 1) {
+                    (true, escaped) => Carry::String(b'"', escaped),
+                    (false, true) => Carry::Escaped,
+                    (false, false) => Carry::Normal,
+                };
+                continue;
+            }
+        }
+
+        for (i, &b) in chunk.iter().enumerate() {
+            let outside = match carry {
+                Carry::Normal => true,
+                Carry::Slash => b != b'/' && b != b'*',
+                _ => false,
+            };
+            let before = carry;
+            carry = step(carry, b, &classes);
+            let quote = quotes && b == b'"' &&
+                        match (before, carry) {
+                            (_, Carry::String(b'"', false)) => outside,
+                            (Carry::String(b'"', false), Carry::Normal) => true,
+                            _ => false,
+                        };
+            if quote || (outside && is_structural(b, &classes)) {
+                positions.push((base + i) as u32);
+            }
+        }
+    }
+
+    match carry {
+        // Let the byte loop find the offending string or comment.
+        Carry::String(..) | Carry::BlockComment(_) => scan_scalar(text, options, quotes),
+        _ => Ok(positions),
+    }
+}
+
+fn step(carry: Carry, b: u8, classes: &Classes) -> Carry {
+    match carry {
+        Carry::Normal => {
+            match b {
+                b'\\' => Carry::Escaped,
+                b'"' => Carry::String(b, false),
+                b'\'' if classes.single_quotes => Carry::String(b, false),
+                b'/' if classes.comments => Carry::Slash,
+                _ => Carry::Normal,
+            }
+        }
+        Carry::Slash => {
+            match b {
+                b'/' => Carry::LineComment,
+                b'*' => Carry::BlockComment(false),
+                _ => step(Carry::Normal, b, classes),
+            }
+        }
+        Carry::Escaped => Carry::Normal,
+        Carry::String(quote, true) => Carry::String(quote, false),
+        Carry::String(quote, false) => {
+            match b {
+                b'\\' => Carry::String(quote, true),
+                b if b == quote => Carry::Normal,
+                _ => carry,
+            }
+        }
+        Carry::LineComment if b == b'\n' || b == b'\r' => Carry::Normal,
+        Carry::LineComment => carry,
+        Carry::BlockComment(true) if b == b'/' => Carry::Normal,
+        Carry::BlockComment(_) => Carry::BlockComment(b == b'*'),
+    }
+}
+
+fn is_structural(b: u8, classes: &Classes) -> bool {
+    match b {
+        b'{' | b'}' | b'[' | b']' | b',' | b':' => true,
+        b'(' | b')' => classes.parens,
+        _ => false,
+    }
+}
+
+/// The bytes escaped by a backslash, given the backslashes of a block;
+/// `carry` is 1 if the block's first byte is escaped and is set for the
+/// next block.
+fn escaped_bits(backslash: u64, carry: &mut u64) -> u64 {
+    let backslash = backslash & !*carry;
+    let follows_escape = (backslash << 1) | *carry;
+    let odd_starts = backslash & !EVEN_BITS & !follows_escape;
+    let (even_starts, overflow) = odd_starts.overflowing_add(backslash);
+    *carry = overflow as u64;
+    (EVEN_BITS ^ (even_starts << 1)) & follows_escape
+}
+
+/// Bit `i` is the XOR of bits `0..=i`.
+fn prefix_xor(mut bits: u64) -> u64 {
+    bits ^= bits << 1;
+    bits ^= bits << 2;
+    bits ^= bits << 4;
+    bits ^= bits << 8;
+    bits ^= bits << 16;
+    bits ^= bits << 32;
+    bits
+}
+
+fn classify_portable(block: &[u8; 64], classes: &Classes) -> Masks {
+    let mut masks = Masks::default();
+    for (i, &b) in block.iter().enumerate() {
+        let bit = 1 << i;
+        match b {
+            b'"' => masks.quote |= bit,
+            b'\\' => masks.backslash |= bit,
+            b'\'' if classes.single_quotes => masks.special |= bit,
+            b'/' if classes.comments => masks.special |= bit,
+            b if is_structural(b, classes) => masks.structural |= bit,
+            _ => {}
+        }
+    }
+    masks
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use super::{Classes, Masks};
+
+    pub fn classify_sse2(block: &[u8; 64], classes: &Classes) -> Masks {
+        // SSE2 is part of the x86_64 baseline.
+        unsafe { sse2(block, classes) }
+    }
+
+    /// Only chosen once AVX2 has been detected.
+    pub fn classify_avx2(block: &[u8; 64], classes: &Classes) -> Masks {
+        unsafe { avx2(block, classes) }
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn sse2(block: &[u8; 64], classes: &Classes) -> Masks {
+        let mut masks = Masks::default();
+        for lane in 0..4 {
+            let v = _mm_loadu_si128(block.as_ptr().add(lane * 16) as *const __m128i);
+            let eq = |b: u8| _mm_movemask_epi8(_mm_cmpeq_epi8(v, _mm_set1_epi8(b as i8)));
+            // `[` and `{`, and `]` and `}`, differ only in bit 5.
+            let folded = _mm_or_si128(v, _mm_set1_epi8(0x20));
+            let fold = |b: u8| _mm_movemask_epi8(_mm_cmpeq_epi8(folded, _mm_set1_epi8(b as i8)));
+            let mut structural = fold(b'{') | fold(b'}') | eq(b',') | eq(b':');
+            if classes.parens {
+                structural |= eq(b'(') | eq(b')');
+            }
+            let mut special = 0;
+            if classes.single_quotes {
+                special |= eq(b'\'');
+            }
+            if classes.comments {
+                special |= eq(b'/');
+            }
+            let shift = lane * 16;
+            masks.quote |= (eq(b'"') as u16 as u64) << shift;
+            masks.backslash |= (eq(b'\\') as u16 as u64) << shift;
+            masks.structural |= (structural as u16 as u64) << shift;
+            masks.special |= (special as u16 as u64) << shift;
+        }
+        masks
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn avx2(block: &[u8; 64], classes: &Classes) -> Masks {
+        let mut masks = Masks::default();
+        for lane in 0..2 {
+            let v = _mm256_loadu_si256(block.as_ptr().add(lane * 32) as *const __m256i);
+            let folded = _mm256_or_si256(v, _mm256_set1_epi8(0x20));
+            macro_rules! eq {
+                ($v:expr, $b:expr) => {
+                    _mm256_movemask_epi8(_mm256_cmpeq_epi8($v, _mm256_set1_epi8($b as i8)))
+                }
+            }
+            let mut structural = eq!(folded, b'{') | eq!(folded, b'}') | eq!(v, b',') |
+                                 eq!(v, b':');
+            if classes.parens {
+                structural |= eq!(v, b'(') | eq!(v, b')');
+            }
+            let mut special = 0;
+            if classes.single_quotes {
+                special |= eq!(v, b'\'');
+            }
+            if classes.comments {
+                special |= eq!(v, b'/');
+            }
+            let shift = lane * 32;
+            masks.quote |= (eq!(v, b'"') as u32 as u64) << shift;
+            masks.backslash |= (eq!(v, b'\\') as u32 as u64) << shift;
+            masks.structural |= (structural as u32 as u64) << shift;
+            masks.special |= (special as u32 as u64) << shift;
+        }
+        masks
+    }
+}
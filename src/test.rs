@@ -1,5 +1,14 @@
 use json_flex;
 use json_flex::{JFObject, Unwrap};
+use {decode_borrowed, decode_into, decode_ndjson_parallel, decode_parallel, decode_spanned,
+     decode_with, decode_with_comments, decode_with_report, find_json, recover, recover_with,
+     repair, structural_index, structural_index_with, unwrap_jsonp, ArrayHoles, Cst, Decimal,
+     Document, DuplicateKeys, ErrorKind, Event, IncrementalParser, JFObjectBuilder, JFValue,
+     Lazy, Limit, Number, Options, ParseLimits, Parser, PathSegment, Placement, RepairKind,
+     ScanBackend, SpannedValue, Visitor, WarningKind};
+#[cfg(feature = "tokio")]
+use {decode_async, ndjson_stream};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
@@ -793,3 +802,1573 @@ fn default() {
 
 
 }
+
+#[test]
+fn json5() {
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let jf = decode_with(r#"{
+        // comments
+        unquoted: 'and you can quote me on that',
+        singleQuotes: 'I can use "double quotes" here',
+        lineBreaks: "Look, Mom! \
+No \\n's!",
+        hexadecimal: 0xdecaf,
+        leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+        positiveSign: +1,
+        trailingComma: 'in objects', andIn: ['arrays',],
+        "backwardsCompatible": "with JSON",
+    }"#.to_owned(), &Options::json5()).unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf["unquoted"].unwrap_string(), "and you can quote me on that");
+    assert_eq!(jf["singleQuotes"].unwrap_string(), r#"I can use "double quotes" here"#);
+    assert_eq!(jf["lineBreaks"].unwrap_string(), r#"Look, Mom! No \n's!"#);
+    assert_eq!(*jf["hexadecimal"].unwrap_i64(), 912559);
+    assert_eq!(*jf["leadingDecimalPoint"].unwrap_f64(), 0.8675309);
+    assert_eq!(*jf["andTrailing"].unwrap_f64(), 8675309.0);
+    assert_eq!(*jf["positiveSign"].unwrap_i64(), 1);
+    assert_eq!(jf["andIn"].to_json(), r#"["arrays"]"#);
+    assert_eq!(jf["backwardsCompatible"].unwrap_string(), "with JSON");
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let jf = decode_with("[+Infinity, -Infinity, NaN, -0x10, /* block */ 1e3]".to_owned(),
+                         &Options::json5())
+                 .unwrap();
+    println!("{:?}", jf);
+    assert_eq!(*jf[0].unwrap_f64(), f64::INFINITY);
+    assert_eq!(*jf[1].unwrap_f64(), f64::NEG_INFINITY);
+    assert!(jf[2].unwrap_f64().is_nan());
+    assert_eq!(*jf[3].unwrap_i64(), -16);
+    assert_eq!(*jf[4].unwrap_f64(), 1000.0);
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let jf = decode_with("\u{feff}{\u{a0}a\u{2003}:\u{b}'\\x41\\u0042\\v'\u{2028}}".to_owned(),
+                         &Options::json5())
+                 .unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf["a"].unwrap_string(), "AB\u{b}");
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let err = decode_with("{a: 1}".to_owned(), &Options::default()).unwrap_err();
+    println!("{}", err);
+    assert_eq!(err.kind, ErrorKind::UnexpectedCharacter('a'));
+    assert_eq!((err.position.line, err.position.column), (1, 2));
+    let err = decode_with("[1, 2] // done".to_owned(), &Options::default()).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::TrailingCharacters);
+    let err = decode_with("[1, 2,, 3]".to_owned(), &Options::json5()).unwrap_err();
+    assert_eq!(err.position.offset, 6);
+
+    // 5
+    // -------------------------------------------------------------------------------
+    println!("--- [ 5 ] -----------------------------------------------------------------");
+    let jf = decode_with(r#"["a\"b", [,], 1.5e3, 'x']"#.to_owned(), &Options::default())
+                 .unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf[0].unwrap_string(), "a\"b");
+    assert_eq!(jf[1].to_json(), "[null,null]");
+    assert_eq!(*jf[2].unwrap_f64(), 1500.0);
+}
+
+#[test]
+fn jsonc() {
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let jf = decode_with(r#"{
+        // Editor
+        "editor.fontSize": 14, /* px */
+        "files.exclude": { "**/.git": true, },
+    }"#.to_owned(), &Options::jsonc()).unwrap();
+    println!("{:?}", jf);
+    assert_eq!(*jf["editor.fontSize"].unwrap_i64(), 14);
+    assert_eq!(jf["files.exclude"].to_json(), r#"{"**/.git":true}"#);
+    assert!(decode_with(r#"{"a": 1 // x
+    }"#.to_owned(), &Options::default()).is_err());
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let (jf, comments) = decode_with_comments(r#"// settings
+{
+    // leading
+    "a": [1, /* one */
+          2],
+    "b": /* value */ null, // trailing
+    "c": [
+        // empty
+    ]
+}
+// end"#.to_owned(), &Options::jsonc()).unwrap();
+    for c in &comments {
+        println!("{:?}", c);
+    }
+    let found: Vec<(&str, Vec<PathSegment>, Placement)> = comments.iter()
+        .map(|c| (&c.text[..], c.path.clone(), c.placement))
+        .collect();
+    let key = |k: &str| PathSegment::Key(k.to_owned());
+    assert_eq!(found,
+               vec![("// settings", vec![], Placement::Before),
+                    ("// leading", vec![key("a")], Placement::Before),
+                    ("/* one */", vec![key("a"), PathSegment::Index(0)], Placement::After),
+                    ("/* value */", vec![key("b")], Placement::Before),
+                    ("// trailing", vec![key("b")], Placement::After),
+                    ("// empty", vec![key("c")], Placement::Inside),
+                    ("// end", vec![], Placement::After)]);
+    assert_eq!((comments[1].position.line, comments[1].position.column), (3, 5));
+    assert_eq!(*jf.get_path(&comments[2].path).unwrap().unwrap_i64(), 1);
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let (jf2, comments) = decode_with_comments("[1, // one\n2]".to_owned(), &Options::jsonc())
+                              .unwrap();
+    let jsonc = jf2.to_jsonc(&comments);
+    println!("{}", jsonc);
+    assert_eq!(jsonc, "[\n    1, // one\n    2\n]");
+    let jsonc = jf.to_jsonc(&[]);
+    let (again, _) = decode_with_comments(jsonc, &Options::jsonc()).unwrap();
+    assert_eq!(again.to_json().len(), jf.to_json().len());
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let text = r#"{"say \"hi\"": "C:\\dir\n\"x\"", // quoted
+ "a\\b": ["\t\u0001"]}"#;
+    let (jf, comments) = decode_with_comments(text.to_owned(), &Options::jsonc()).unwrap();
+    let jsonc = jf.to_jsonc(&comments);
+    println!("{}", jsonc);
+    let (again, again_comments) = decode_with_comments(jsonc, &Options::jsonc()).unwrap();
+    assert_eq!(again["say \"hi\""].unwrap_string(), "C:\\dir\n\"x\"");
+    assert_eq!(again["a\\b"][0].unwrap_string(), "\t\u{1}");
+    assert_eq!(again_comments[0].text, "// quoted");
+}
+
+#[test]
+fn barewords() {
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let mut options = Options { unquoted_keys: true, ..Options::default() };
+    let jf = decode_with("{id: 3, $type: 'user', 0: null}".to_owned(), &options).unwrap();
+    println!("{:?}", jf);
+    assert_eq!(*jf["id"].unwrap_i64(), 3);
+    assert_eq!(jf["$type"].unwrap_string(), "user");
+    assert!(jf["0"].is_null());
+    assert!(decode_with("{id: 3, status: active}".to_owned(), &options).is_err());
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    options.bareword_values = true;
+    let jf = decode_with("{id: 3, status: active, ok: true, none: null, note: not found }"
+                             .to_owned(),
+                         &options)
+                 .unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf["status"].unwrap_string(), "active");
+    assert!(jf["ok"].is_true());
+    assert!(jf["none"].is_null());
+    assert_eq!(jf["note"].unwrap_string(), "not found");
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let jf = decode_with("[2021-01-01, 12:30, 1.5, -2, v1.2, @home,\n trueish]".to_owned(),
+                         &options)
+                 .unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf.to_json(), r#"["2021-01-01","12:30",1.5,-2,"v1.2","@home","trueish"]"#);
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let jf = decode_with("{list: [a, b,], // c\n last: x}".to_owned(), &Options::lenient())
+                 .unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf["list"].to_json(), r#"["a","b"]"#);
+    assert_eq!(jf["last"].unwrap_string(), "x");
+}
+
+#[test]
+fn dialects() {
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let jf = decode_with(r#"{'name': 'x\xe9', 'ok': True, 'bad': False, 'none': None, 'pair': (1, 2.5), 'one': (1,), 'empty': (), 'f': [nan, -inf]}"#.to_owned(),
+                         &Options::python())
+                 .unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf["name"].unwrap_string(), "x\u{e9}");
+    assert!(jf["ok"].is_true());
+    assert!(jf["bad"].is_false());
+    assert!(jf["none"].is_null());
+    assert_eq!(jf["pair"].to_json(), "[1,2.5]");
+    assert_eq!(jf["one"].to_json(), "[1]");
+    assert_eq!(jf["empty"].to_json(), "[]");
+    assert!(jf["f"][0].unwrap_f64().is_nan());
+    assert_eq!(*jf["f"][1].unwrap_f64(), f64::NEG_INFINITY);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let jf = decode_with("{ a: undefined, b: NaN, c: -Infinity, d: [ 1, 'two' ] }".to_owned(),
+                         &Options::javascript())
+                 .unwrap();
+    println!("{:?}", jf);
+    assert!(jf["a"].is_null());
+    assert!(jf["b"].unwrap_f64().is_nan());
+    assert_eq!(*jf["c"].unwrap_f64(), f64::NEG_INFINITY);
+    assert_eq!(jf["d"].to_json(), r#"[1,"two"]"#);
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    assert!(decode_with("[True]".to_owned(), &Options::javascript()).is_err());
+    assert!(decode_with("[undefined]".to_owned(), &Options::python()).is_err());
+    assert!(decode_with("(1, 2)".to_owned(), &Options::default()).is_err());
+    assert!(decode_with("(1, 2]".to_owned(), &Options::python()).is_err());
+    let jf = decode_with("[(a, b), None]".to_owned(), &Options::lenient()).unwrap();
+    assert_eq!(jf.to_json(), r#"[["a","b"],null]"#);
+}
+
+#[test]
+fn repairs() {
+    let kinds = |text: &str| -> (String, Vec<RepairKind>) {
+        let (jf, repairs) = repair(text);
+        println!("{} => {} {:?}", text, jf.to_json(), repairs);
+        (jf.to_json(), repairs.iter().map(|r| r.kind).collect())
+    };
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    assert_eq!(kinds(r#"[1, 2, {"b": "hello"#),
+               (r#"[1,2,{"b":"hello"}]"#.to_owned(),
+                vec![RepairKind::ClosedString, RepairKind::ClosedObject, RepairKind::ClosedArray]));
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    assert_eq!(kinds("[1 2 \"3\"]"),
+               (r#"[1,2,"3"]"#.to_owned(),
+                vec![RepairKind::InsertedComma, RepairKind::InsertedComma]));
+    assert_eq!(kinds(r#"{"a": 1 "b" 2}"#).1,
+               vec![RepairKind::InsertedComma, RepairKind::InsertedColon]);
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    assert_eq!(kinds(r#"{"a": 1, "b":"#),
+               (r#"{"a":1}"#.to_owned(), vec![RepairKind::DroppedKey, RepairKind::ClosedObject]));
+    assert_eq!(kinds(r#"{"a": 1, "b", "c": }"#),
+               (r#"{"a":1}"#.to_owned(), vec![RepairKind::DroppedKey, RepairKind::DroppedKey]));
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let (jf, repairs) = repair(r#"{"a": [1, 2}"#);
+    assert_eq!(jf["a"].to_json(), "[1,2]");
+    assert_eq!(repairs[0].kind, RepairKind::ClosedArray);
+    assert_eq!(repairs[0].position.column, 12);
+    assert_eq!(kinds("[tru").0, "[true]");
+    assert_eq!(kinds("[1, @@, 2]"),
+               ("[1]".to_owned(), vec![RepairKind::DroppedInput, RepairKind::ClosedArray]));
+    assert_eq!(kinds(r#"{"a":1}}"#).1, vec![RepairKind::DroppedInput]);
+    assert_eq!(kinds(r#"[1, 2]"#), ("[1,2]".to_owned(), vec![]));
+    assert_eq!(kinds("").0, "null");
+
+    // 5
+    // -------------------------------------------------------------------------------
+    println!("--- [ 5 ] -----------------------------------------------------------------");
+    let (jf, repairs) = repair(&format!("[{}", "1 ".repeat(200_000)));
+    assert_eq!(jf.unwrap_vec().len(), 200_000);
+    assert_eq!(repairs.len(), 200_000);
+    assert_eq!(repairs[199_998].position.column, 400_000);
+}
+
+#[test]
+fn embedded() {
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let log = r#"2024-05-01 [INFO] request done {"status": 200, "tags": ["a"]} in 3ms"#;
+    let found = find_json(log);
+    println!("{:?}", found);
+    assert_eq!(found.len(), 1);
+    assert_eq!(&log[found[0].range.clone()], r#"{"status": 200, "tags": ["a"]}"#);
+    assert_eq!(*found[0].value["status"].unwrap_i64(), 200);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let markdown = "Result:\n\n```json\n{\"a\": \"}\"}\n```\n\nand also [1, 2] or [see docs].";
+    let found = find_json(markdown);
+    println!("{:?}", found);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].value["a"].unwrap_string(), "}");
+    assert_eq!(found[1].value.to_json(), "[1,2]");
+    assert_eq!(&markdown[found[1].range.clone()], "[1, 2]");
+    assert!(find_json("no json { here").is_empty());
+    let found = find_json(r#"[ {"a": 1} oops ] {"b": [2, 3] "#);
+    let ranges: Vec<_> = found.iter().map(|f| f.range.clone()).collect();
+    assert_eq!(ranges, vec![2..10, 24..30]);
+    let unclosed = "[".repeat(40_000);
+    assert!(find_json(&unclosed).is_empty());
+    let nested = format!("{}1 x{}", "[".repeat(20_000), "]".repeat(20_000));
+    assert!(find_json(&nested).is_empty());
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let (callback, jf) = unwrap_jsonp("/**/ jQuery_123.cb({\"ok\": true, n: [1, 2]});\n").unwrap();
+    println!("{} {:?}", callback, jf);
+    assert_eq!(callback, "jQuery_123.cb");
+    assert!(jf["ok"].is_true());
+    assert!(unwrap_jsonp("{\"ok\": true}").is_none());
+    assert!(unwrap_jsonp("cb({\"ok\": true}) trailing").is_none());
+    let (callback, jf) = unwrap_jsonp("«cb({\"a\": 1})").unwrap();
+    assert_eq!((callback.as_str(), jf["a"].unwrap_i64()), ("cb", &1));
+    assert_eq!(unwrap_jsonp("«été({\"a\": 1})").unwrap().0, "été");
+}
+
+#[test]
+fn report() {
+    let kinds = |text: &str, options: &Options| -> Vec<WarningKind> {
+        let (_, warnings) = decode_with_report(text.to_owned(), options).unwrap();
+        for w in &warnings {
+            println!("{}", w);
+        }
+        warnings.iter().map(|w| w.kind).collect()
+    };
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let (jf, warnings) = decode_with_report("\u{feff}[,,1,,]".to_owned(), &Options::default()).unwrap();
+    assert_eq!(jf.to_json(), "[null,null,1,null,null]");
+    assert_eq!(warnings.iter().map(|w| w.kind).collect::<Vec<_>>(),
+               vec![WarningKind::ByteOrderMark,
+                    WarningKind::ArrayHole,
+                    WarningKind::ArrayHole,
+                    WarningKind::ArrayHole,
+                    WarningKind::ArrayHole]);
+    assert_eq!(warnings[3].position.offset, 8);
+    assert_eq!(warnings[3].snippet, ",");
+    let skip = Options {
+        array_holes: ArrayHoles::Skip,
+        ..Options::default()
+    };
+    let (jf, warnings) = decode_with_report("[1,,2]".to_owned(), &skip).unwrap();
+    assert_eq!(jf.to_json(), "[1,2]");
+    assert_eq!(warnings.iter().map(|w| w.kind).collect::<Vec<_>>(), vec![WarningKind::ArrayHole]);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let (_, warnings) = decode_with_report("{'a': 'x\\q',\n 'b': 1}".to_owned(), &Options::default())
+        .unwrap();
+    assert_eq!(warnings.iter().map(|w| w.kind).collect::<Vec<_>>(),
+               vec![WarningKind::SingleQuotes,
+                    WarningKind::Escape,
+                    WarningKind::SingleQuotes,
+                    WarningKind::SingleQuotes]);
+    assert_eq!(warnings[1].snippet, "\\q");
+    assert_eq!((warnings[3].position.line, warnings[3].position.column), (2, 2));
+    assert_eq!(warnings[3].snippet, "'b'");
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    assert_eq!(kinds("{a: +0x1F, b: .5, c: Infinity, /* c */ d: [1,],}", &Options::json5()),
+               vec![WarningKind::UnquotedKey,
+                    WarningKind::NumberSyntax,
+                    WarningKind::UnquotedKey,
+                    WarningKind::NumberSyntax,
+                    WarningKind::UnquotedKey,
+                    WarningKind::NonFiniteNumber,
+                    WarningKind::Comment,
+                    WarningKind::UnquotedKey,
+                    WarningKind::TrailingComma,
+                    WarningKind::TrailingComma]);
+    assert_eq!(kinds("(True, None, 007)", &Options::python()),
+               vec![WarningKind::Tuple,
+                    WarningKind::Literal,
+                    WarningKind::Literal,
+                    WarningKind::NumberSyntax]);
+    assert_eq!(kinds("{status: 2021-01-01}", &Options::lenient()),
+               vec![WarningKind::UnquotedKey, WarningKind::Bareword]);
+    assert_eq!(kinds("[\"a\tb\", \"\\ud800\"]", &Options::default()),
+               vec![WarningKind::ControlCharacter, WarningKind::LoneSurrogate]);
+    assert_eq!(kinds(r#"{"a": [1, 2.5e3, "x\u00e9"], "b": null}"#, &Options::default()),
+               vec![]);
+}
+
+#[test]
+fn error_messages() {
+    let message = |text: &str| decode_with(text.to_owned(), &Options::default()).unwrap_err().to_string();
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let m = message("{\"a\": 1,\n    \"b\": 2\n    \"c\": 3}");
+    println!("{}", m);
+    assert_eq!(m,
+               "unexpected character '\"' at line 3 column 5\n  |\n3 |     \"c\": 3}\n  |     ^\n  = expected \
+                ',' or '}'\n  = hint: missing comma between object members");
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let err = decode_with(r#"["say "hi""]"#.to_owned(), &Options::default()).unwrap_err();
+    assert_eq!(err.expected, vec!["','", "']'"]);
+    assert_eq!(err.hint, Some("unescaped quote inside string"));
+    assert_eq!(err.caret, 7);
+    assert!(message("[1, 2\n").ends_with("hint: a bracket is never closed"));
+    assert!(message(r#"{"a" 1}"#).contains("= expected ':'\n"));
+    assert!(message("[1, 2}").contains("closing bracket does not match"));
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let err = decode_with(format!("[{}1 2]", "1, ".repeat(40)), &Options::default()).unwrap_err();
+    println!("{}", err);
+    assert_eq!(err.position.column, 124);
+    assert!(err.source_line.starts_with("...") && err.source_line.ends_with(" 2]"));
+    assert_eq!(err.source_line.chars().nth(err.caret), Some('2'));
+}
+
+#[test]
+fn recovery() {
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let (jf, errors) = recover("{\"a\": 1, b: 2,\n \"c\": [1 2, 3], \"d\": \"x\\uZZ\"}");
+    for e in &errors {
+        println!("{}", e);
+    }
+    assert_eq!(*jf["a"].unwrap_i64(), 1);
+    assert_eq!(jf["c"].to_json(), "[1,3]");
+    assert_eq!(jf["d"].unwrap_string(), "x\u{fffd}ZZ");
+    assert!(jf.get_path(&[PathSegment::Key("b".to_owned())]).is_none());
+    assert_eq!(errors.iter().map(|e| (e.kind.clone(), e.position.line)).collect::<Vec<_>>(),
+               vec![(ErrorKind::UnexpectedCharacter('b'), 1),
+                    (ErrorKind::UnexpectedCharacter('2'), 2),
+                    (ErrorKind::InvalidEscape, 2)]);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let (jf, errors) = recover(r#"[{"k": "v,w" x}, @, 5"#);
+    assert_eq!(jf.to_json(), r#"[{"k":"v,w"},5]"#);
+    assert_eq!(errors.iter().map(|e| e.kind.clone()).collect::<Vec<_>>(),
+               vec![ErrorKind::UnexpectedCharacter('x'),
+                    ErrorKind::UnexpectedCharacter('@'),
+                    ErrorKind::UnexpectedEndOfInput]);
+    let (jf, errors) = recover(r#"{"a": [1, 2}"#);
+    assert_eq!(jf["a"].to_json(), "[1,2]");
+    assert_eq!(errors.len(), 1);
+    let (jf, errors) = recover("[1, 2]");
+    assert_eq!((jf.to_json(), errors.len()), ("[1,2]".to_owned(), 0));
+    assert!(recover("tru, 1").0.is_null());
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let (jf, errors) = recover(&format!("[{}1]", "1 1, ".repeat(200_000)));
+    assert_eq!(jf.unwrap_vec().len(), 200_001);
+    assert_eq!(errors.len(), 200_000);
+    assert_eq!(errors[199_999].position.column, 999_999);
+    assert_eq!(errors[199_999].source_line, format!("...{}1, 1]", "1, 1 ".repeat(8)));
+    assert_eq!(errors[199_999].caret, 43);
+}
+
+#[test]
+fn limits() {
+    let limited = |limits: ParseLimits| {
+        Options {
+            limits,
+            ..Options::default()
+        }
+    };
+    let exceeded = |text: &str, options: &Options| -> Option<Limit> {
+        match decode_with(text.to_owned(), options) {
+            Err(e) => {
+                println!("{}", e);
+                match e.kind {
+                    ErrorKind::LimitExceeded(limit) => Some(limit),
+                    _ => None,
+                }
+            }
+            Ok(_) => None,
+        }
+    };
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let options = limited(ParseLimits::untrusted());
+    let deep = "[".repeat(100_000);
+    assert_eq!(exceeded(&deep, &options), Some(Limit::Depth));
+    let err = decode_with(deep, &options).unwrap_err();
+    assert_eq!(err.position.offset, 128);
+    let ok = format!("{}{}", "[".repeat(128), "]".repeat(128));
+    assert!(decode_with(ok, &options).is_ok());
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let options = limited(ParseLimits {
+        max_string_length: Some(3),
+        max_number_length: Some(4),
+        max_nodes: Some(4),
+        max_input_bytes: Some(30),
+        ..ParseLimits::default()
+    });
+    assert_eq!(exceeded(r#"["abcd"]"#, &options), Some(Limit::StringLength));
+    assert_eq!(exceeded(r#"{"abcd": 1}"#, &options), Some(Limit::StringLength));
+    assert_eq!(exceeded("[12345]", &options), Some(Limit::NumberLength));
+    assert_eq!(exceeded("[1, 2, 3, 4]", &options), Some(Limit::Nodes));
+    assert_eq!(exceeded(&format!("[{}]", " ".repeat(30)), &options), Some(Limit::InputBytes));
+    assert!(decode_with(r#"["abc", 1234, [-1]]"#.to_owned(), &options).is_err());
+    assert!(decode_with(r#"["abc", 1234, -1]"#.to_owned(), &options).is_ok());
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let options = limited(ParseLimits {
+        max_depth: Some(2),
+        ..ParseLimits::default()
+    });
+    let (jf, errors) = recover_with("[1, x, [[1]]]", &options);
+    assert!(jf.is_null());
+    assert_eq!(errors.iter().map(|e| e.kind.clone()).collect::<Vec<_>>(),
+               vec![ErrorKind::UnexpectedCharacter('x'), ErrorKind::LimitExceeded(Limit::Depth)]);
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let nested = |depth: usize| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+    for &depth in &[600, 2000] {
+        assert_eq!(json_flex::decode(nested(depth)).to_json(), nested(depth));
+        assert_eq!(decode_with(nested(depth), &Options::default()).unwrap().to_json(),
+                   nested(depth));
+    }
+    assert_eq!(exceeded(&nested(2000), &limited(ParseLimits::untrusted())), Some(Limit::Depth));
+}
+
+#[test]
+fn duplicate_keys() {
+    let text = r#"{"a": 1, "b": [2], "a": 3, "b": [4], "a": 5}"#;
+    let policy = |duplicate_keys: DuplicateKeys| {
+        Options {
+            duplicate_keys,
+            ..Options::default()
+        }
+    };
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let jf = decode_with(text.to_owned(), &Options::default()).unwrap();
+    assert_eq!((*jf["a"].unwrap_i64(), jf["b"].to_json()), (5, "[4]".to_owned()));
+    let jf = decode_with(text.to_owned(), &policy(DuplicateKeys::FirstWins)).unwrap();
+    assert_eq!((*jf["a"].unwrap_i64(), jf["b"].to_json()), (1, "[2]".to_owned()));
+    let jf = decode_with(text.to_owned(), &policy(DuplicateKeys::Collect)).unwrap();
+    assert_eq!((jf["a"].to_json(), jf["b"].to_json()), ("[1,3,5]".to_owned(), "[[2],[4]]".to_owned()));
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let err = decode_with(text.to_owned(), &policy(DuplicateKeys::Error)).unwrap_err();
+    println!("{}", err);
+    assert_eq!(err.kind, ErrorKind::DuplicateKey("a".to_owned()));
+    assert_eq!(err.position.column, 20);
+    assert!(decode_with(r#"{"a": {"a": 1}, "b": 2}"#.to_owned(), &policy(DuplicateKeys::Error)).is_ok());
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let (_, warnings) = decode_with_report(text.to_owned(), &Options::default()).unwrap();
+    assert_eq!(warnings.iter().map(|w| (w.kind, w.snippet.as_str())).collect::<Vec<_>>(),
+               vec![(WarningKind::DuplicateKey, "\"a\""),
+                    (WarningKind::DuplicateKey, "\"b\""),
+                    (WarningKind::DuplicateKey, "\"a\"")]);
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let text = r#"{"a": 1, "a": [2, @], "b": 3}"#;
+    let options = policy(DuplicateKeys::Error);
+    let err = decode_with(text.to_owned(), &options).unwrap_err();
+    assert_eq!((err.kind, err.position.column), (ErrorKind::DuplicateKey("a".to_owned()), 10));
+    let err = decode_with_report(text.to_owned(), &options).unwrap_err();
+    assert_eq!((err.kind, err.position.column), (ErrorKind::DuplicateKey("a".to_owned()), 10));
+    let (jf, errors) = recover_with(text, &options);
+    assert_eq!((*jf["a"].unwrap_i64(), *jf["b"].unwrap_i64()), (1, 3));
+    assert_eq!(errors.iter().map(|e| (e.kind.clone(), e.position.column)).collect::<Vec<_>>(),
+               vec![(ErrorKind::DuplicateKey("a".to_owned()), 10),
+                    (ErrorKind::UnexpectedCharacter('@'), 19)]);
+}
+
+#[test]
+fn array_holes() {
+    let holes = |text: &str, array_holes: ArrayHoles, trailing_commas: bool| -> Option<String> {
+        let options = Options {
+            array_holes,
+            trailing_commas,
+            ..Options::default()
+        };
+        decode_with(text.to_owned(), &options).ok().map(|jf| jf.to_json())
+    };
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    assert_eq!(json_flex::decode("[1,,,2]".to_owned()).to_json(), "[1,null,null,2]");
+    assert_eq!(holes("[1,,,2]", ArrayHoles::Null, false), Some("[1,null,null,2]".to_owned()));
+    assert_eq!(holes("[1,,,2]", ArrayHoles::Skip, false), Some("[1,2]".to_owned()));
+    assert_eq!(holes("[1,,,2]", ArrayHoles::Reject, true), None);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    assert_eq!(holes("[1,2,,]", ArrayHoles::Null, true), Some("[1,2,null]".to_owned()));
+    assert_eq!(holes("[,]", ArrayHoles::Null, true), Some("[null]".to_owned()));
+    assert_eq!(holes("[,]", ArrayHoles::Null, false), Some("[null,null]".to_owned()));
+    assert_eq!(holes("[,]", ArrayHoles::Skip, false), Some("[]".to_owned()));
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let options = Options {
+        array_holes: ArrayHoles::Skip,
+        ..Options::default()
+    };
+    let (jf, warnings) = decode_with_report("[\"a\",\n,\"c\"]".to_owned(), &options).unwrap();
+    assert_eq!(jf.to_json(), r#"["a","c"]"#);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, WarningKind::ArrayHole);
+    assert_eq!((warnings[0].position.line, warnings[0].position.column), (2, 1));
+}
+
+#[test]
+fn spans() {
+    let text = "{\n  \"name\": \"demo\",\n  \"ports\": [80, \"x\"],\n  \"tls\": {\"on\": true}\n}";
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let doc = decode_spanned(text.to_owned(), &Options::default()).unwrap();
+    println!("{:?}", doc);
+    assert_eq!((doc.span.start.offset, doc.span.end.offset), (0, text.len()));
+    let port = doc.get_path(&[PathSegment::Key("ports".to_owned()), PathSegment::Index(1)]).unwrap();
+    assert_eq!(port.value, SpannedValue::String("x".to_owned()));
+    assert_eq!((port.span.start.line, port.span.start.column), (3, 17));
+    assert_eq!(&text[port.span.start.offset..port.span.end.offset], "\"x\"");
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let key = doc.get_key("tls").unwrap();
+    assert_eq!((key.span.start.line, key.span.start.column, key.span.end.column), (4, 3, 8));
+    let tls = doc.get("tls").unwrap();
+    assert_eq!(&text[tls.span.start.offset..tls.span.end.offset], "{\"on\": true}");
+    assert_eq!(doc.get_path(&[PathSegment::Key("tls".to_owned()), PathSegment::Key("on".to_owned())])
+                   .unwrap()
+                   .value,
+               SpannedValue::True);
+    assert_eq!(doc.to_jfobject()["ports"].to_json(), r#"[80,"x"]"#);
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let text = r#"{"a": 1, "b": {"a": 0}, "a": 2, "a": [3]}"#;
+    let spanned = |duplicate_keys| {
+        let options = Options { duplicate_keys, ..Options::default() };
+        (decode_spanned(text.to_owned(), &options), decode_with(text.to_owned(), &options))
+    };
+    let (doc, error) = spanned(DuplicateKeys::Error);
+    assert_eq!(doc.unwrap_err(), error.unwrap_err());
+    for &policy in &[DuplicateKeys::FirstWins, DuplicateKeys::LastWins, DuplicateKeys::Collect] {
+        let (doc, expected) = spanned(policy);
+        let (doc, expected) = (doc.unwrap(), expected.unwrap());
+        for key in &["a", "b"] {
+            assert_eq!(doc.to_jfobject()[*key].to_json(), expected[*key].to_json());
+        }
+        match doc.value {
+            SpannedValue::Dictionary(ref members) => assert_eq!(members.len(), 2),
+            _ => panic!("not an object"),
+        }
+    }
+    let doc = spanned(DuplicateKeys::FirstWins).0.unwrap();
+    assert_eq!(doc.get_key("a").unwrap().span.start.column, 2);
+    assert_eq!(doc.get("a").unwrap().value, SpannedValue::Integer(1));
+    let doc = spanned(DuplicateKeys::LastWins).0.unwrap();
+    assert_eq!(doc.get_key("a").unwrap().span.start.column, 33);
+    match doc.value {
+        SpannedValue::Dictionary(ref members) => assert_eq!(members[0].0.value, "b"),
+        _ => panic!("not an object"),
+    }
+    let doc = spanned(DuplicateKeys::Collect).0.unwrap();
+    let a = doc.get("a").unwrap();
+    assert_eq!(&text[a.span.start.offset..a.span.end.offset], &text[6..text.len() - 1]);
+    assert_eq!(doc.to_jfobject()["a"].to_json(), "[1,2,[3]]");
+}
+
+#[test]
+fn cst() {
+    let key = |k: &str| PathSegment::Key(k.to_owned());
+    let text = "{\n    // release\n    \"version\": \"1.2.3\",\n    \"ratio\":   1.50,\n    \"tags\": [\"a\"] /* keep */\n}\n";
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let mut cst = Cst::parse(text.to_owned(), &Options::jsonc()).unwrap();
+    assert_eq!(cst.to_string(), text);
+    assert_eq!(cst.source(&[key("ratio")]), Some("1.50"));
+    assert!(cst.set(&[key("version")], &JFObject::String("1.2.4".to_owned())));
+    assert_eq!(cst.to_string(), text.replace("1.2.3", "1.2.4"));
+    assert_eq!(cst.set_raw(&[key("ratio")], "2.50"), Ok(true));
+    assert_eq!(cst.source(&[key("ratio")]), Some("2.50"));
+    assert!(cst.set_raw(&[key("ratio")], "1, 2").is_err());
+    assert!(!cst.set(&[key("missing")], &JFObject::Null));
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    assert!(cst.insert(&[], "name", &JFObject::String("say \"hi\"".to_owned())));
+    assert!(cst.push(&[key("tags")], &JFObject::Integer(2)));
+    println!("{}", cst);
+    assert_eq!(cst.to_string(),
+               "{\n    // release\n    \"version\": \"1.2.4\",\n    \"ratio\":   2.50,\n    \"tags\": [\"a\", 2], \
+                /* keep */\n    \"name\": \"say \\\"hi\\\"\"\n}\n");
+    assert_eq!(cst.to_jfobject()["name"].unwrap_string(), "say \"hi\"");
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    assert!(cst.remove(&[key("ratio")]));
+    assert!(cst.remove(&[key("name")]));
+    assert!(cst.remove(&[key("tags"), PathSegment::Index(0)]));
+    assert_eq!(cst.to_string(),
+               "{\n    // release\n    \"version\": \"1.2.4\",\n    \"tags\": [2]\n}\n");
+    assert!(!cst.remove(&[]));
+}
+
+#[test]
+fn raw_numbers() {
+    let options = Options {
+        raw_numbers: true,
+        ..Options::default()
+    };
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = "[0.1000, 9007199254740993, 18446744073709551615, -12, 1E+2]";
+    let jf = decode_with(text.to_owned(), &options).unwrap();
+    println!("{:?}", jf);
+    assert_eq!(jf.to_json(), text.replace(", ", ","));
+    let n = jf[0].unwrap_number();
+    assert_eq!((n.as_str(), n.as_f64(), n.as_i64(), n.is_integer()), ("0.1000", 0.1, None, false));
+    assert_eq!(jf[1].unwrap_number().as_i64(), Some(9007199254740993));
+    assert_eq!(jf[2].unwrap_number().as_u64(), Some(u64::MAX));
+    assert_eq!(jf[2].unwrap_number().as_i64(), None);
+    assert_eq!((jf[3].unwrap_number().as_i64(), jf[3].unwrap_number().as_u64()), (Some(-12), None));
+    assert_eq!(jf[4].unwrap_number().as_f64(), 100.0);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let options = Options {
+        raw_numbers: true,
+        ..Options::json5()
+    };
+    let jf = decode_with("[+0x1F, -0x8000000000000000, .5, Infinity]".to_owned(), &options).unwrap();
+    assert_eq!(jf[0].unwrap_number().as_str(), "+0x1F");
+    assert_eq!(jf[0].unwrap_number().as_i64(), Some(31));
+    assert_eq!(jf[1].unwrap_number().as_i64(), Some(i64::MIN));
+    assert_eq!(jf[2].unwrap_number().as_f64(), 0.5);
+    assert!(jf[3].is_float());
+    assert_eq!(Number::from(7i64).as_str(), "7");
+    assert!(decode_with("[1]".to_owned(), &Options::default()).unwrap()[0].is_integer());
+}
+
+#[test]
+fn decimals() {
+    let options = Options {
+        decimals: true,
+        ..Options::default()
+    };
+
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = "[19.99, 0.1000, -0.05, 1.5e3, 2E-3, 42, 18446744073709551615, 1e400]";
+    let jf = decode_with(text.to_owned(), &options).unwrap();
+    println!("{:?}", jf);
+    let d = jf[0].unwrap_decimal();
+    assert_eq!((d.mantissa(), d.scale(), d.to_f64()), (1999, 2, 19.99));
+    assert_eq!((jf[1].unwrap_decimal().mantissa(), jf[1].unwrap_decimal().scale()), (1000, 4));
+    assert_eq!(jf[2].unwrap_decimal().to_string(), "-0.05");
+    assert_eq!(jf[3].unwrap_decimal().to_string(), "1500");
+    assert_eq!(jf[4].unwrap_decimal().to_string(), "0.002");
+    assert_eq!(jf[5].unwrap_i64(), &42);
+    assert!(jf[6].is_float());
+    assert!(jf[7].is_float());
+    assert_eq!(jf.to_json(), "[19.99,0.1000,-0.05,1500,0.002,42,18446744073709552000,inf]");
+    let text = "[0e99999999999, 0.00e-5, 1e-400000000, 5e-38, 5e-39, 1e-9223372036854775808]";
+    let jf = decode_with(text.to_owned(), &options).unwrap();
+    assert_eq!((jf[0].unwrap_decimal().mantissa(), jf[0].unwrap_decimal().scale()), (0, 0));
+    assert_eq!(jf[1].unwrap_decimal().to_string(), "0.0000000");
+    assert_eq!(jf[2].unwrap_f64(), &0.0);
+    assert_eq!(jf[3].unwrap_decimal().scale(), Decimal::MAX_SCALE);
+    assert!(jf[4].is_float());
+    assert!(jf[5].is_float());
+    let jf = decode_with("[-0.0, 0.0, -0e5, -0.50]".to_owned(), &options).unwrap();
+    assert_eq!(jf.to_json(), "[-0.0,0.0,-0,-0.50]");
+    let (negative, positive) = (jf[0].unwrap_decimal(), jf[1].unwrap_decimal());
+    assert!(negative.is_sign_negative() && !positive.is_sign_negative());
+    assert!(negative.to_f64().is_sign_negative());
+    assert_ne!(negative, positive);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    assert_eq!(Decimal::new(5, 3).to_string(), "0.005");
+    assert_eq!(Decimal::new(-12345, 2).to_string(), "-123.45");
+    let options = Options {
+        decimals: true,
+        ..Options::json5()
+    };
+    let jf = decode_with("[.5, +1., 0x10]".to_owned(), &options).unwrap();
+    assert_eq!(jf[0].unwrap_decimal(), &Decimal::new(5, 1));
+    assert_eq!(jf[1].unwrap_decimal(), &Decimal::new(1, 0));
+    assert_eq!(jf[2].unwrap_i64(), &16);
+}
+
+#[test]
+fn borrowed() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = r#"{"name": "plain", "escaped": "a\nb", "list": [1, 2.5, null, true], 'k': 'v'}"#;
+    let jf = decode_borrowed(text, &Options::default()).unwrap();
+    println!("{:?}", jf);
+    match jf.unwrap_hashmap().get("name") {
+        Some(&JFValue::String(Cow::Borrowed(s))) => assert_eq!(s, "plain"),
+        other => panic!("{:?}", other),
+    }
+    match jf["escaped"] {
+        JFValue::String(Cow::Owned(ref s)) => assert_eq!(s, "a\nb"),
+        ref other => panic!("{:?}", other),
+    }
+    assert!(jf.unwrap_hashmap().keys().all(|k| match *k {
+        Cow::Borrowed(_) => true,
+        Cow::Owned(_) => false,
+    }));
+    assert_eq!(jf["list"][0].unwrap_i64(), &1);
+    assert_eq!(jf["list"][1].unwrap_f64(), &2.5);
+    assert!(jf["list"][2].is_null() && jf["list"][3].is_true());
+    assert_eq!(jf["k"].unwrap_string(), "v");
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let owned = jf.clone().into_owned();
+    assert_eq!(owned["escaped"].unwrap_string(), "a\nb");
+    assert_eq!(owned["list"].to_json(), jf["list"].to_json());
+    let options = Options {
+        duplicate_keys: DuplicateKeys::Collect,
+        ..Options::default()
+    };
+    let jf = decode_borrowed(r#"{"a": 1, "a": 2}"#, &options).unwrap();
+    assert_eq!(jf["a"].to_json(), "[1,2]");
+    assert!(decode_borrowed("[1,", &Options::default()).is_err());
+}
+
+#[test]
+fn document() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = r#"{"id": 7, "tags": ["a", "b\n"], "meta": {"score": 1.5, "ok": true}, "none": null}"#;
+    let doc = Document::parse(text, &Options::default()).unwrap();
+    let root = doc.root();
+    assert_eq!((root.len(), doc.len()), (4, 15));
+    assert_eq!(root.get("id").unwrap().unwrap_i64(), 7);
+    let tags = root.get("tags").unwrap();
+    assert_eq!((tags.len(), tags.at(1).unwrap().unwrap_string()), (2, "b\n"));
+    assert!(tags.at(2).is_none());
+    assert_eq!(root.get("meta").unwrap().get("score").unwrap().unwrap_f64(), 1.5);
+    assert!(root.get("meta").unwrap().get("ok").unwrap().is_true());
+    assert!(root.get("none").unwrap().is_null() && root.get("missing").is_none());
+    let keys: Vec<&str> = root.members().map(|m| m.0).collect();
+    assert_eq!(keys, ["id", "tags", "meta", "none"]);
+    let jf = doc.to_jfobject();
+    assert_eq!(jf["meta"]["score"].unwrap_f64(), &1.5);
+    assert_eq!(jf["tags"].to_json(), tags.to_json());
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let mut doc = Document::default();
+    doc.load("[[], {}, 1, [2, [3]], 4]", &Options::default()).unwrap();
+    let values: Vec<String> = doc.root().elements().map(|e| e.to_json()).collect();
+    assert_eq!(values, ["[]", "{}", "1", "[2,[3]]", "4"]);
+    doc.load(r#"{"a": 1, "a": 2}"#, &Options::default()).unwrap();
+    assert_eq!((doc.root().len(), doc.root().get("a").unwrap().unwrap_i64()), (2, 2));
+    assert!(doc.load("[1,", &Options::default()).is_err());
+    assert!(doc.is_empty() && doc.root().is_null());
+}
+
+#[test]
+fn lazy() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = r#"{"data": [{"x": "}]"}, [1, [2]], "skip"],
+                   "meta": {"id": 42, "tags": ["a", "b",]}, "bad": [1 2]}"#;
+    let options = Options {
+        trailing_commas: true,
+        ..Options::default()
+    };
+    let lazy = Lazy::new(text, &options).unwrap();
+    let root = lazy.root();
+    assert!(root.is_dictionary().unwrap() && !root.is_array().unwrap());
+    let meta = root.get("meta").unwrap().unwrap();
+    assert_eq!(meta.get("id").unwrap().unwrap().decode().unwrap().unwrap_i64(), &42);
+    let tags = meta.get("tags").unwrap().unwrap();
+    assert_eq!(tags.elements().unwrap().len(), 2);
+    assert_eq!(tags.at(1).unwrap().unwrap().decode().unwrap().unwrap_string(), "b");
+    let path = [PathSegment::Key("data".to_owned()),
+                PathSegment::Index(0),
+                PathSegment::Key("x".to_owned())];
+    assert_eq!(root.get_path(&path).unwrap().unwrap().decode().unwrap().unwrap_string(), "}]");
+    assert!(root.get("missing").unwrap().is_none());
+    let keys: Vec<String> = root.members().unwrap().into_iter().map(|m| m.0.into_owned()).collect();
+    assert_eq!(keys, ["data", "meta", "bad"]);
+    assert!(root.get("bad").unwrap().unwrap().decode().is_err());
+    assert!(root.decode().is_err());
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let options = Options {
+        comments: true,
+        ..Options::default()
+    };
+    let lazy = Lazy::new("[1, , 3] // done", &options).unwrap();
+    let values: Vec<String> =
+        lazy.root().elements().unwrap().iter().map(|e| e.decode().unwrap().to_json()).collect();
+    assert_eq!(values, ["1", "null", "3"]);
+    assert_eq!(lazy.root().decode().unwrap().to_json(), "[1,null,3]");
+    assert!(Lazy::new("[1, 2}", &Options::default()).is_err());
+    assert!(Lazy::new("[1, 2", &Options::default()).is_err());
+    assert!(Lazy::new("[] []", &Options::default()).unwrap().root().decode().is_err());
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let text = format!("[{}[0]]", "[1], ".repeat(100_000));
+    let lazy = Lazy::new(&text, &Options::default()).unwrap();
+    let root = lazy.root();
+    let mut sum = 0;
+    for i in 0..=100_000 {
+        let inner = root.at(i).unwrap().unwrap();
+        sum += inner.at(0).unwrap().unwrap().decode().unwrap().unwrap_i64();
+    }
+    assert_eq!(sum, 100_000);
+    assert!(root.at(100_001).unwrap().is_none());
+    assert!(root.at(0).unwrap().unwrap().at(1).unwrap().is_none());
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let text = r#"{"a": 1, "b": 2, "a": [3]}"#;
+    let get = |duplicate_keys, key| {
+        let options = Options { duplicate_keys, ..Options::default() };
+        let lazy = Lazy::new(text, &options).unwrap();
+        lazy.root().get(key).map(|v| v.unwrap().decode().unwrap().to_json())
+    };
+    let error = get(DuplicateKeys::Error, "a").unwrap_err();
+    let options = Options { duplicate_keys: DuplicateKeys::Error, ..Options::default() };
+    let expected = decode_with(text.to_owned(), &options).unwrap_err();
+    assert_eq!((error.kind, error.position), (expected.kind, expected.position));
+    assert_eq!(get(DuplicateKeys::Error, "b").unwrap(), "2");
+    assert_eq!(get(DuplicateKeys::FirstWins, "a").unwrap(), "1");
+    assert_eq!(get(DuplicateKeys::LastWins, "a").unwrap(), "[3]");
+    assert_eq!(get(DuplicateKeys::Collect, "a").unwrap(), "[3]");
+
+    let text = "{a: it's, b: [c\"d, e)], c: (1, 2), d: 12:30 // e\n}";
+    let lazy = Lazy::new(text, &Options::lenient()).unwrap();
+    let expected = decode_with(text.to_owned(), &Options::lenient()).unwrap();
+    for key in &["a", "b", "c", "d"] {
+        let value = lazy.root().get(key).unwrap().unwrap().decode().unwrap();
+        assert_eq!(value.to_json(), expected[*key].to_json());
+    }
+    assert_eq!(expected["b"].to_json(), r#"["c\"d","e)"]"#);
+}
+
+#[test]
+fn structural_scan() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = format!(r#"{{"a": "x\"]", "b": [1, {{}}], "{}": ":"}}"#, "\\\\".repeat(40));
+    let index = structural_index(&text, &Options::default()).unwrap();
+    let found: String = index.iter().map(|&i| text.as_bytes()[i as usize] as char).collect();
+    assert_eq!(found, "{:,:[,{}],:}");
+    assert!(ScanBackend::detect().is_available());
+    if !ScanBackend::Sse2.is_available() {
+        assert_eq!(ScanBackend::detect(), ScanBackend::Scalar);
+    }
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let backends = [ScanBackend::Portable, ScanBackend::Sse2, ScanBackend::Avx2];
+    let double_quotes_only = Options {
+        single_quotes: false,
+        ..Options::default()
+    };
+    let option_sets = [double_quotes_only, Options::default(), Options::jsonc(), Options::lenient()];
+    let alphabet = b"{}[](),:\"\\'/*\n ab";
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for round in 0..1000 {
+        let len = round % 300;
+        let text: String = (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                alphabet[(seed >> 33) as usize % alphabet.len()] as char
+            })
+            .collect();
+        for options in &option_sets {
+            let expected = structural_index_with(&text, options, ScanBackend::Scalar);
+            for &backend in &backends {
+                assert_eq!(structural_index_with(&text, options, backend), expected, "{:?}", text);
+            }
+        }
+    }
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    // decode_with takes the strings the scan found whole; it must agree with
+    // decode_into, which does not, wherever the 64-byte blocks fall.
+    let pieces = [r#""plain""#, r#""with \"escapes\" \u0041""#, r#"'single "quoted"'"#,
+                  "// a \"comment\"\n1", r#"/* "block" */ 2"#, "bare word", r#"{"k": "v"}"#,
+                  r#""\\""#, r#""""#];
+    for pad in 0..70 {
+        for (i, piece) in pieces.iter().enumerate() {
+            let rest: Vec<&str> =
+                pieces.iter().cycle().skip(i).take(pieces.len()).cloned().collect();
+            let text = format!("[\"{}\", {}]", "x".repeat(pad), rest.join(", "));
+            let options = Options::lenient();
+            let expected = decode_into(&text, &options, JFObjectBuilder::new(&options)).unwrap();
+            assert_eq!(decode_with(text.clone(), &options).unwrap().to_json(), expected.to_json());
+            let cut = format!("[\"{}\", {}", "x".repeat(pad), piece);
+            let cut = &cut[..cut.len() - 1];
+            for options in &[Options::default(), Options::lenient()] {
+                let expected = decode_into(cut, options, JFObjectBuilder::new(options));
+                assert_eq!(decode_with(cut.to_owned(), options).map(|o| o.to_json()),
+                           expected.map(|o| o.to_json()),
+                           "{:?}",
+                           cut);
+            }
+        }
+    }
+}
+
+#[test]
+fn parallel() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let mut text = String::new();
+    for i in 0..1000 {
+        text.push_str(&format!("{{\"id\": {}, \"tags\": [\"a,b\", \"]\"]}}\r\n", i));
+        if i % 100 == 0 {
+            text.push_str("   \n");
+        }
+    }
+    text.push_str("{\"broken\": }\n[1]");
+    let results = decode_ndjson_parallel(&text, &Options::default(), 4);
+    assert_eq!(results.len(), 1002);
+    for (i, result) in results[..1000].iter().enumerate() {
+        assert_eq!(result.as_ref().unwrap()["id"].unwrap_i64(), &(i as i64));
+    }
+    let error = results[1000].as_ref().unwrap_err();
+    assert_eq!((error.position.line, error.position.column), (1011, 12));
+    assert_eq!(results[1001].as_ref().unwrap().to_json(), "[1]");
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let elements: Vec<String> = (0..500).map(|i| format!("{{\"n\": [{}, \"x,]\"]}}", i)).collect();
+    let text = format!(" [{}] ", elements.join(",\n "));
+    let jf = decode_parallel(text.clone(), &Options::default(), 0).unwrap();
+    assert_eq!(jf.to_json(), decode_with(text, &Options::default()).unwrap().to_json());
+    assert_eq!(jf[499]["n"][0].unwrap_i64(), &499);
+    for text in &["[]", "[1,,2]", "[1, 2,]", "{\"a\": [1, 2]}", "3"] {
+        let options = Options::lenient();
+        let expected = decode_with(text.to_string(), &options).unwrap().to_json();
+        assert_eq!(decode_parallel(text.to_string(), &options, 2).unwrap().to_json(), expected);
+    }
+    let text = "[1, {\"a\": tru}, 3]";
+    let error = decode_parallel(text.to_owned(), &Options::default(), 2).unwrap_err();
+    assert_eq!(error, decode_with(text.to_owned(), &Options::default()).unwrap_err());
+    for text in &["[1] 2", "[1 2]", "[1, {\"a\" 1}]", "[[1, 2], [3 4]]"] {
+        let error = decode_parallel(text.to_string(), &Options::default(), 2).unwrap_err();
+        assert_eq!(error, decode_with(text.to_string(), &Options::default()).unwrap_err());
+    }
+    let error = decode_parallel("[1 2]".to_owned(), &Options::default(), 2).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UnexpectedCharacter('2'));
+    assert_eq!(error.hint, Some("missing comma between array elements"));
+    let mut results = decode_ndjson_parallel("1\n2 3", &Options::default(), 2);
+    let error = results.pop().unwrap().unwrap_err();
+    let serial = decode_with("2 3".to_owned(), &Options::default()).unwrap_err();
+    assert_eq!((error.kind, error.expected, error.hint),
+               (serial.kind, serial.expected, serial.hint));
+    let options = Options {
+        limits: ParseLimits {
+            max_depth: Some(2),
+            ..ParseLimits::default()
+        },
+        ..Options::default()
+    };
+    assert!(decode_parallel("[[1], [2]]".to_owned(), &options, 2).is_ok());
+    assert!(decode_parallel("[[1], [[2]]]".to_owned(), &options, 2).is_err());
+}
+
+#[test]
+fn events() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = "{\"a\": [1, 2.5,, 'x'],\n \"b\": {\"c\": true, \"d\": null}}";
+    let mut parser = Parser::new(text, Options::default());
+    let mut events = Vec::new();
+    while let Some(event) = parser.next_event().unwrap() {
+        let position = parser.position();
+        events.push((event, &text[parser.token()], position.line, position.column));
+    }
+    assert_eq!(events,
+               vec![(Event::StartObject, "{", 1, 1),
+                    (Event::Key(Cow::Borrowed("a")), "\"a\"", 1, 2),
+                    (Event::StartArray, "[", 1, 7),
+                    (Event::Integer(1), "1", 1, 8),
+                    (Event::Float(2.5), "2.5", 1, 11),
+                    (Event::Null, "", 1, 15),
+                    (Event::String(Cow::Borrowed("x")), "'x'", 1, 17),
+                    (Event::EndArray, "]", 1, 20),
+                    (Event::Key(Cow::Borrowed("b")), "\"b\"", 2, 2),
+                    (Event::StartObject, "{", 2, 7),
+                    (Event::Key(Cow::Borrowed("c")), "\"c\"", 2, 8),
+                    (Event::Bool(true), "true", 2, 13),
+                    (Event::Key(Cow::Borrowed("d")), "\"d\"", 2, 19),
+                    (Event::Null, "null", 2, 24),
+                    (Event::EndObject, "}", 2, 28),
+                    (Event::EndObject, "}", 2, 29)]);
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let events: Vec<_> = Parser::new("[1, tru]", Options::default()).collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[1], Ok(Event::Integer(1)));
+    assert_eq!(events[2].as_ref().unwrap_err().position.column, 5);
+    let events: Vec<_> = Parser::new("[1, /* two */ 2,]", Options::jsonc()).collect();
+    assert_eq!(events.len(), 4);
+    assert!(events.iter().all(|e| e.is_ok()));
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let jf = json_flex::decode(r#"{"a": [1, 2"#.to_owned());
+    assert_eq!(jf.to_json(), r#"{"a":[1,2]}"#);
+    let jf = json_flex::decode(r#"[1, 2] trailing"#.to_owned());
+    assert_eq!(jf.to_json(), "[1,2]");
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let jf = json_flex::decode(r#"["a\"b"]"#.to_owned());
+    assert_eq!(jf.to_json(), r#"["a\"b"]"#);
+    let text = r#"{"k\"ey\\": ["q\"uote", "back\\slash", "tab\tnl\n", "\u0001", "é"]}"#;
+    let jf = json_flex::decode(text.to_owned());
+    let json = jf.to_json();
+    assert_eq!(json, r#"{"k\"ey\\":["q\"uote","back\\slash","tab\tnl\n","\u0001","é"]}"#);
+    assert_eq!(json_flex::decode(json.clone()).to_json(), json);
+    assert_eq!(decode_with(json, &Options::default()).unwrap()["k\"ey\\"][0].unwrap_string(),
+               "q\"uote");
+
+    // 5
+    // -------------------------------------------------------------------------------
+    println!("--- [ 5 ] -----------------------------------------------------------------");
+    let text = "{\"caf\u{e9}\": [1, -2.5e3, \"a\\\"b\", '\u{1f600}', true, null],\n \
+                \"n\": {\"deep\": [[]]}, \"bare\": x y // c\n}";
+    let options = Options::lenient();
+    let mut expected = Vec::new();
+    let mut parser = Parser::new(text, options.clone());
+    while let Some(event) = parser.next_event().unwrap() {
+        expected.push((format!("{:?}", event), parser.token(), parser.position()));
+    }
+    for size in 1..8 {
+        let mut parser = Parser::from_reader(Trickle(text.as_bytes(), size), options.clone());
+        let mut events = Vec::new();
+        while let Some(event) = parser.next_event().unwrap() {
+            events.push((format!("{:?}", event), parser.token(), parser.position()));
+        }
+        assert_eq!(events, expected, "{}", size);
+    }
+    let mut parser = Parser::from_reader(Trickle(b"[1, 2 3]", 2), Options::default());
+    while let Ok(Some(_)) = parser.next_event() {}
+    let error = parser.next_event().unwrap_err();
+    let expected = decode_with("[1, 2 3]".to_owned(), &Options::default()).unwrap_err();
+    assert_eq!(error, expected);
+    let mut parser = Parser::from_reader(Trickle(b"[\"a\xff\"]", 3), Options::default());
+    assert_eq!(parser.next_event(), Ok(Some(Event::StartArray)));
+    assert_eq!(parser.next_event().unwrap_err().kind, ErrorKind::InvalidUtf8);
+
+    let numbers: Vec<String> = (0..50_000).map(|i| i.to_string()).collect();
+    let text = format!("[{}]", numbers.join(",\n"));
+    let mut parser = Parser::from_reader(Trickle(text.as_bytes(), 1000), Options::default());
+    let mut sum = 0;
+    while let Some(event) = parser.next_event().unwrap() {
+        if let Event::Integer(i) = event {
+            sum += i;
+        }
+    }
+    assert_eq!(sum, 49_999 * 50_000 / 2);
+    assert_eq!((parser.position().line, parser.position().column), (50_000, 6));
+    assert_eq!(parser.token(), text.len() - 1..text.len());
+}
+
+/// Hands out a slice `.1` bytes per read.
+struct Trickle<'a>(&'a [u8], usize);
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+        let n = self.1.min(buf.len()).min(self.0.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+/// Collects the `price` of every record into one column.
+#[derive(Default)]
+struct PriceColumn {
+    depth: usize,
+    in_price: bool,
+    prices: Vec<f64>,
+}
+
+impl<'a> Visitor<'a> for PriceColumn {
+    type Output = Vec<f64>;
+
+    fn start_object(&mut self) -> Result<(), ErrorKind> {
+        self.depth += 1;
+        Ok(())
+    }
+    fn end_object(&mut self) -> Result<(), ErrorKind> {
+        self.depth -= 1;
+        Ok(())
+    }
+    fn start_array(&mut self) -> Result<(), ErrorKind> {
+        self.depth += 1;
+        Ok(())
+    }
+    fn end_array(&mut self) -> Result<(), ErrorKind> {
+        self.depth -= 1;
+        Ok(())
+    }
+    fn key(&mut self, key: Cow<'a, str>) -> Result<(), ErrorKind> {
+        self.in_price = self.depth == 2 && key == "price";
+        Ok(())
+    }
+    fn string(&mut self, _: Cow<'a, str>) -> Result<(), ErrorKind> {
+        match self.in_price {
+            true => Err(ErrorKind::Rejected("price must be a number")),
+            false => Ok(()),
+        }
+    }
+    fn integer(&mut self, value: i64) -> Result<(), ErrorKind> {
+        self.float(value as f64)
+    }
+    fn float(&mut self, value: f64) -> Result<(), ErrorKind> {
+        if self.in_price {
+            self.prices.push(value);
+        }
+        Ok(())
+    }
+    fn bool(&mut self, _: bool) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+    fn null(&mut self) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+    fn finish(self) -> Vec<f64> {
+        self.prices
+    }
+}
+
+#[test]
+fn visitor() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = r#"[{"name": "a", "price": 1.5}, {"price": 2, "tags": {"price": 9}},
+                   {"name": "c", "price": 12345678901234567890}]"#;
+    let prices = decode_into(text, &Options::default(), PriceColumn::default()).unwrap();
+    assert_eq!(prices, vec![1.5, 2.0, 12345678901234567890.0]);
+    let options = Options { decimals: true, ..Options::default() };
+    let prices = decode_into("[{\"price\": 0.25}]", &options, PriceColumn::default()).unwrap();
+    assert_eq!(prices, vec![0.25]);
+
+    let text = "[{\"price\": 1},\n {\"price\": \"free\"}]";
+    let error = decode_into(text, &Options::default(), PriceColumn::default()).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Rejected("price must be a number"));
+    assert_eq!((error.position.line, error.position.column), (2, 12));
+    assert_eq!(error.to_string().lines().next().unwrap(), "price must be a number at line 2 column 12");
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let text = r#"{"a": 1, "b": [true, null, {"c": "x"}], "a": 2, "a": 3}"#;
+    for &policy in &[DuplicateKeys::LastWins, DuplicateKeys::FirstWins, DuplicateKeys::Collect] {
+        let options = Options { duplicate_keys: policy, ..Options::default() };
+        let jf = decode_into(text, &options, JFObjectBuilder::new(&options)).unwrap();
+        let (expected, _) = decode_with_report(text.to_owned(), &options).unwrap();
+        assert_eq!(jf["a"].to_json(), expected["a"].to_json());
+        assert_eq!(jf["b"].to_json(), expected["b"].to_json());
+    }
+    let options = Options { duplicate_keys: DuplicateKeys::Error, ..Options::default() };
+    let error = decode_into(text, &options, JFObjectBuilder::new(&options)).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::DuplicateKey("a".to_owned()));
+    assert_eq!(error.position.column, 41);
+}
+
+#[test]
+fn incremental() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let values = ["{\"s\": [\"caf\u{e9} \\u00e9\\\"\", -12.5e3, 7]}", "\"\u{1f600}\"", "12345", "true", "[]"];
+    let text = format!("{}\n{} {}\t{}\r\n{}", values[0], values[1], values[2], values[3], values[4]);
+    let expected: Vec<String> = values.iter()
+        .map(|v| decode_with(v.to_string(), &Options::default()).unwrap().to_json())
+        .collect();
+    let bytes = text.as_bytes();
+    let collect = |chunks: &[&[u8]]| {
+        let mut parser = IncrementalParser::default();
+        let mut values = Vec::new();
+        for chunk in chunks {
+            values.extend(parser.feed(chunk));
+        }
+        values.extend(parser.finish());
+        values.into_iter().map(|v| v.unwrap().to_json()).collect::<Vec<String>>()
+    };
+    for split in 0..bytes.len() {
+        assert_eq!(collect(&[&bytes[..split], &bytes[split..]]), expected, "{}", split);
+    }
+    let chunks: Vec<&[u8]> = bytes.chunks(1).collect();
+    assert_eq!(collect(&chunks), expected);
+
+    let mut parser = IncrementalParser::default();
+    assert!(parser.feed(b"[1, 2").is_empty());
+    assert_eq!(parser.feed(b"]  3").len(), 1);
+    assert_eq!(parser.feed(b"4").len(), 0);
+    assert_eq!(parser.feed(b"\n").pop().unwrap().unwrap().to_json(), "34");
+    assert!(parser.finish().is_none());
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let mut parser = IncrementalParser::new(&Options::jsonc());
+    let mut values = parser.feed(b"/* [ */ {\"a\": \"/*\" // }\n, \"b\": 1} // ]\n");
+    values.extend(parser.feed(b"[2] /"));
+    values.extend(parser.feed(b"/ x\n"));
+    assert!(parser.finish().is_none());
+    let json: Vec<String> = values.into_iter().map(|v| v.unwrap()["b"].to_json()).take(1).collect();
+    assert_eq!(json, vec!["1"]);
+    let mut parser = IncrementalParser::new(&Options::jsonc());
+    assert!(parser.feed(b"1 /* never closed").len() == 1);
+    assert_eq!(parser.finish().unwrap().unwrap_err().kind, ErrorKind::UnterminatedComment);
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let mut parser = IncrementalParser::default();
+    let mut values = parser.feed(b"[1]\n  [1, tr");
+    values.extend(parser.feed(b"ue, nul]\n\"\xff\" [4"));
+    assert_eq!(values.len(), 3);
+    assert!(values[0].is_ok());
+    let error = values[1].as_ref().unwrap_err();
+    assert_eq!((error.position.line, error.position.column, error.position.offset), (2, 13, 16));
+    let error = values[2].as_ref().unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidUtf8);
+    assert_eq!((error.position.line, error.position.column), (3, 2));
+    assert_eq!(parser.finish().unwrap().unwrap_err().kind, ErrorKind::UnexpectedEndOfInput);
+
+    let options = Options {
+        limits: ParseLimits {
+            max_input_bytes: Some(8),
+            ..ParseLimits::default()
+        },
+        ..Options::default()
+    };
+    let mut parser = IncrementalParser::new(&options);
+    assert_eq!(parser.feed(b"[1] [2, 3, 4, 5").len(), 2);
+    assert!(parser.feed(b"]").is_empty());
+    assert!(parser.finish().is_none());
+
+    // 4
+    // -------------------------------------------------------------------------------
+    println!("--- [ 4 ] -----------------------------------------------------------------");
+    let no_tuples = Options { python_literals: false, ..Options::lenient() };
+    let cases = [(Options::lenient(),
+                  "x y\n{a: [it's, c\"d, e)]}\n1 2 (1, 2)\r1x 2 // c\rtrue false",
+                  vec!["x y", "{a: [it's, c\"d, e)]}", "1", "2", "(1, 2)", "1x 2", "true false"]),
+                 (no_tuples, "[f(, 2]\n[3] g)", vec!["[f(, 2]", "[3]", "g)"]),
+                 (Options::jsonc(), "1 // one\r2 // two\r\n[3]", vec!["1", "2", "[3]"])];
+    for &(ref options, text, ref values) in &cases {
+        let expected: Vec<String> = values.iter()
+            .map(|v| decode_with(v.to_string(), options).unwrap().to_json())
+            .collect();
+        let bytes = text.as_bytes();
+        for split in 0..bytes.len() {
+            let mut parser = IncrementalParser::new(options);
+            let mut values = parser.feed(&bytes[..split]);
+            values.extend(parser.feed(&bytes[split..]));
+            values.extend(parser.finish());
+            let values: Vec<String> = values.into_iter().map(|v| v.unwrap().to_json()).collect();
+            assert_eq!(values, expected, "{:?} split at {}", text, split);
+        }
+    }
+}
+
+/// Runs `future` to completion on this thread, parked while it waits, so that
+/// the tests need no runtime.
+#[cfg(feature = "tokio")]
+fn block_on<F: ::std::future::Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(Unpark(thread::current())).into();
+    let mut context = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// The reading end of an in-memory pipe of `capacity` bytes, with `chunks`
+/// written to the other end from another thread and the pipe closed after.
+#[cfg(feature = "tokio")]
+fn piped(chunks: Vec<&'static [u8]>, capacity: usize) -> ::tokio::io::DuplexStream {
+    use tokio::io::AsyncWriteExt;
+
+    let (reader, mut writer) = ::tokio::io::duplex(capacity);
+    ::std::thread::spawn(move || {
+        for chunk in chunks {
+            // The reader may stop early, closing the pipe.
+            let _ = block_on(writer.write_all(chunk));
+        }
+    });
+    reader
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn async_decode() {
+    // 1
+    // -------------------------------------------------------------------------------
+    println!("--- [ 1 ] -----------------------------------------------------------------");
+    let text = "{\"name\": \"caf\u{e9}\", \"tags\": [1, 2.5, null]}";
+    let reader = piped(vec![&text.as_bytes()[..10], &text.as_bytes()[10..]], 3);
+    let jf = block_on(decode_async(reader, &Options::default())).unwrap();
+    assert_eq!(jf["name"].unwrap_string(), "caf\u{e9}");
+    assert_eq!(jf["tags"].to_json(), "[1,2.5,null]");
+
+    let reader = piped(vec![b"[1,\n", b" tru]"], 4);
+    let error = block_on(decode_async(reader, &Options::default())).unwrap_err();
+    assert_eq!((error.position.line, error.position.column), (2, 2));
+    let reader = piped(vec![b"[\"\xe9\"]"], 4);
+    let error = block_on(decode_async(reader, &Options::default())).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidUtf8);
+    let options = Options {
+        limits: ParseLimits {
+            max_input_bytes: Some(16),
+            ..ParseLimits::default()
+        },
+        ..Options::default()
+    };
+    let reader = piped(vec![b"[1, 2, 3, 4, 5, 6, 7, 8, 9]"], 4);
+    let error = block_on(decode_async(reader, &options)).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::LimitExceeded(Limit::InputBytes));
+
+    // 2
+    // -------------------------------------------------------------------------------
+    println!("--- [ 2 ] -----------------------------------------------------------------");
+    let chunks: Vec<&'static [u8]> = vec![b"{\"id\": 1}\n\n  \n{\"id\"", b": 2}\r\n{\"id\": }\n",
+                                           b"[1, 2, 3, 4, 5, 6, 7, 8, 9]\n\"\xff\"\n{\"id\": 3}"];
+    let reader = ::tokio::io::BufReader::with_capacity(5, piped(chunks, 7));
+    let mut stream = ndjson_stream(reader, &options);
+    let mut results = Vec::new();
+    while let Some(result) = block_on(stream.next_value()) {
+        results.push(result);
+    }
+    assert_eq!(results.len(), 6);
+    assert_eq!(results[0].as_ref().unwrap()["id"].unwrap_i64(), &1);
+    assert_eq!(results[1].as_ref().unwrap()["id"].unwrap_i64(), &2);
+    let error = results[2].as_ref().unwrap_err();
+    assert_eq!((error.position.line, error.position.column, error.position.offset), (5, 8, 32));
+    let error = results[3].as_ref().unwrap_err();
+    assert_eq!(error.kind, ErrorKind::LimitExceeded(Limit::InputBytes));
+    assert_eq!((error.position.line, error.position.offset), (6, 34));
+    let error = results[4].as_ref().unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidUtf8);
+    assert_eq!((error.position.line, error.position.offset), (7, 63));
+    assert_eq!(results[5].as_ref().unwrap()["id"].unwrap_i64(), &3);
+
+    // 3
+    // -------------------------------------------------------------------------------
+    println!("--- [ 3 ] -----------------------------------------------------------------");
+    let chunks: Vec<&'static [u8]> = vec![b"[1, 2, 3, 4, 50]\n[1, 2, 3, 4, 50]\r\n",
+                                           b"[1, 2, 3, 4, 500]\n[1, 2, 3, 4, 50]"];
+    let reader = ::tokio::io::BufReader::with_capacity(5, piped(chunks, 7));
+    let mut stream = ndjson_stream(reader, &options);
+    let mut results = Vec::new();
+    while let Some(result) = block_on(stream.next_value()) {
+        results.push(result.err().map(|e| e.kind));
+    }
+    assert_eq!(results,
+               vec![None, None, Some(ErrorKind::LimitExceeded(Limit::InputBytes)), None]);
+}
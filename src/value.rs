@@ -0,0 +1,261 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Index;
+
+use error::DecodeError;
+use json_flex::{JFObject, Unwrap};
+use number::{Decimal, Number};
+use options::Options;
+use parser::Parser;
+use visitor::{build_tree, Node, Tree};
+
+/// `JFObject` borrowing from the input: strings and keys without escapes are
+/// slices of it rather than copies.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JFValue<'a> {
+    String(Cow<'a, str>),
+    Integer(i64),
+    Float(f64),
+    Number(Number),
+    Decimal(Decimal),
+    Dictionary(HashMap<Cow<'a, str>, JFValue<'a>>),
+    Array(Vec<JFValue<'a>>),
+    Null,
+    False,
+    True,
+}
+
+/// Like `decode_with`, borrowing strings from `text` where it can.
+pub fn decode_borrowed<'a>(text: &'a str, options: &Options) -> Result<JFValue<'a>, DecodeError> {
+    let mut parser = Parser::new(text, options.clone());
+    build_tree(&mut parser, |_, _| {})
+}
+
+impl<'a> JFValue<'a> {
+    pub fn into_string(&self) -> Option<&str> {
+        match *self {
+            JFValue::String(ref v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_i64(&self) -> Option<&i64> {
+        match *self {
+            JFValue::Integer(ref v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_f64(&self) -> Option<&f64> {
+        match *self {
+            JFValue::Float(ref v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_number(&self) -> Option<&Number> {
+        match *self {
+            JFValue::Number(ref v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_decimal(&self) -> Option<&Decimal> {
+        match *self {
+            JFValue::Decimal(ref v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_hashmap(&self) -> Option<&HashMap<Cow<'a, str>, JFValue<'a>>> {
+        match *self {
+            JFValue::Dictionary(ref v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn into_vec(&self) -> Option<&Vec<JFValue<'a>>> {
+        match *self {
+            JFValue::Array(ref v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        matches!(*self, JFValue::Null)
+    }
+    pub fn is_true(&self) -> bool {
+        matches!(*self, JFValue::True)
+    }
+    pub fn is_false(&self) -> bool {
+        matches!(*self, JFValue::False)
+    }
+    pub fn is_array(&self) -> bool {
+        matches!(*self, JFValue::Array(_))
+    }
+    pub fn is_dictionary(&self) -> bool {
+        matches!(*self, JFValue::Dictionary(_))
+    }
+    pub fn is_string(&self) -> bool {
+        matches!(*self, JFValue::String(_))
+    }
+    pub fn is_integer(&self) -> bool {
+        matches!(*self, JFValue::Integer(_))
+    }
+    pub fn is_float(&self) -> bool {
+        matches!(*self, JFValue::Float(_))
+    }
+    pub fn is_number(&self) -> bool {
+        matches!(*self, JFValue::Number(_))
+    }
+    pub fn is_decimal(&self) -> bool {
+        matches!(*self, JFValue::Decimal(_))
+    }
+    pub fn unwrap_string(&self) -> &str {
+        self.into_string().unwrap()
+    }
+    pub fn unwrap_i64(&self) -> &i64 {
+        self.into_i64().unwrap()
+    }
+    pub fn unwrap_f64(&self) -> &f64 {
+        self.into_f64().unwrap()
+    }
+    pub fn unwrap_number(&self) -> &Number {
+        self.into_number().unwrap()
+    }
+    pub fn unwrap_decimal(&self) -> &Decimal {
+        self.into_decimal().unwrap()
+    }
+    pub fn unwrap_hashmap(&self) -> &HashMap<Cow<'a, str>, JFValue<'a>> {
+        self.into_hashmap().unwrap()
+    }
+    pub fn unwrap_vec(&self) -> &Vec<JFValue<'a>> {
+        self.into_vec().unwrap()
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_jfobject().to_json()
+    }
+
+    /// A copy that owns all of its strings.
+    pub fn to_jfobject(&self) -> JFObject {
+        self.clone().into_owned()
+    }
+
+    pub fn into_owned(self) -> JFObject {
+        match self {
+            JFValue::String(v) => JFObject::String(v.into_owned()),
+            JFValue::Integer(v) => JFObject::Integer(v),
+            JFValue::Float(v) => JFObject::Float(v),
+            JFValue::Number(v) => JFObject::Number(v),
+            JFValue::Decimal(v) => JFObject::Decimal(v),
+            JFValue::Dictionary(v) => {
+                JFObject::Dictionary(v.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect())
+            }
+            JFValue::Array(v) => JFObject::Array(v.into_iter().map(JFValue::into_owned).collect()),
+            JFValue::Null => JFObject::Null,
+            JFValue::False => JFObject::False,
+            JFValue::True => JFObject::True,
+        }
+    }
+}
+
+impl<'a> Tree for JFValue<'a> {
+    type Key = Cow<'a, str>;
+
+    fn array() -> JFValue<'a> {
+        JFValue::Array(Vec::new())
+    }
+    fn object() -> JFValue<'a> {
+        JFValue::Dictionary(HashMap::new())
+    }
+    fn scalar(value: JFObject) -> JFValue<'a> {
+        match value {
+            JFObject::Integer(i) => JFValue::Integer(i),
+            JFObject::Float(f) => JFValue::Float(f),
+            JFObject::Number(n) => JFValue::Number(n),
+            JFObject::Decimal(d) => JFValue::Decimal(d),
+            JFObject::True => JFValue::True,
+            JFObject::False => JFValue::False,
+            _ => JFValue::Null,
+        }
+    }
+    fn as_vec_mut(&mut self) -> Option<&mut Vec<JFValue<'a>>> {
+        match *self {
+            JFValue::Array(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+    fn as_map_mut(&mut self) -> Option<&mut HashMap<Cow<'a, str>, JFValue<'a>>> {
+        match *self {
+            JFValue::Dictionary(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Node<'a> for JFValue<'a> {
+    fn key(name: Cow<'a, str>) -> Cow<'a, str> {
+        name
+    }
+    fn string(value: Cow<'a, str>) -> JFValue<'a> {
+        JFValue::String(value)
+    }
+}
+
+impl<'a> Unwrap<Cow<'a, str>> for JFValue<'a> {
+    fn unwrap(self) -> Cow<'a, str> {
+        match self {
+            JFValue::String(s) => s,
+            _ => panic!(),
+        }
+    }
+}
+impl<'a> Unwrap<i64> for JFValue<'a> {
+    fn unwrap(self) -> i64 {
+        match self {
+            JFValue::Integer(i) => i,
+            _ => panic!(),
+        }
+    }
+}
+impl<'a> Unwrap<f64> for JFValue<'a> {
+    fn unwrap(self) -> f64 {
+        match self {
+            JFValue::Float(f) => f,
+            _ => panic!(),
+        }
+    }
+}
+impl<'a> Unwrap<HashMap<Cow<'a, str>, JFValue<'a>>> for JFValue<'a> {
+    fn unwrap(self) -> HashMap<Cow<'a, str>, JFValue<'a>> {
+        match self {
+            JFValue::Dictionary(d) => d,
+            _ => panic!(),
+        }
+    }
+}
+impl<'a> Unwrap<Vec<JFValue<'a>>> for JFValue<'a> {
+    fn unwrap(self) -> Vec<JFValue<'a>> {
+        match self {
+            JFValue::Array(a) => a,
+            _ => panic!(),
+        }
+    }
+}
+
+impl<'a> Index<usize> for JFValue<'a> {
+    type Output = JFValue<'a>;
+    fn index(&self, id: usize) -> &JFValue<'a> {
+        self.unwrap_vec().get(id).unwrap()
+    }
+}
+
+impl<'a> Index<String> for JFValue<'a> {
+    type Output = JFValue<'a>;
+    fn index(&self, id: String) -> &JFValue<'a> {
+        &self[id.as_str()]
+    }
+}
+
+impl<'a> Index<&str> for JFValue<'a> {
+    type Output = JFValue<'a>;
+    fn index(&self, id: &str) -> &JFValue<'a> {
+        self.unwrap_hashmap().get(id).unwrap()
+    }
+}
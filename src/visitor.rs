@@ -0,0 +1,310 @@
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use error::{DecodeError, ErrorKind};
+use json_flex::JFObject;
+use number::{Decimal, Number};
+use options::{DuplicateKeys, Options};
+use parser::{Event, Parser};
+
+/// Callbacks for the events of a document, in input order. An `Err` stops
+/// decoding with a `DecodeError` at the token being visited; custom visitors
+/// can use `ErrorKind::Rejected`.
+pub trait Visitor<'a> {
+    type Output;
+
+    fn start_object(&mut self) -> Result<(), ErrorKind>;
+    fn end_object(&mut self) -> Result<(), ErrorKind>;
+    fn start_array(&mut self) -> Result<(), ErrorKind>;
+    fn end_array(&mut self) -> Result<(), ErrorKind>;
+    fn key(&mut self, key: Cow<'a, str>) -> Result<(), ErrorKind>;
+    fn string(&mut self, value: Cow<'a, str>) -> Result<(), ErrorKind>;
+    fn integer(&mut self, value: i64) -> Result<(), ErrorKind>;
+    fn float(&mut self, value: f64) -> Result<(), ErrorKind>;
+    /// With `Options::raw_numbers`; passed on to `float` unless overridden.
+    fn number(&mut self, value: Number) -> Result<(), ErrorKind> {
+        self.float(value.as_f64())
+    }
+    /// With `Options::decimals`; passed on to `float` unless overridden.
+    fn decimal(&mut self, value: Decimal) -> Result<(), ErrorKind> {
+        self.float(value.to_f64())
+    }
+    fn bool(&mut self, value: bool) -> Result<(), ErrorKind>;
+    fn null(&mut self) -> Result<(), ErrorKind>;
+    /// Called after the last event, for the result of `decode_into`.
+    fn finish(self) -> Self::Output;
+}
+
+/// Decodes `text` into whatever `visitor` makes of it.
+pub fn decode_into<'a, V>(text: &'a str,
+                          options: &Options,
+                          mut visitor: V)
+                          -> Result<V::Output, DecodeError>
+    where V: Visitor<'a>
+{
+    visit(&mut Parser::new(text, options.clone()), &mut visitor)?;
+    Ok(visitor.finish())
+}
+
+/// Hands the rest of `parser`'s events to `visitor`, such as after reading
+/// the first few with `next_event`.
+pub fn visit<'a, V>(parser: &mut Parser<'a>, visitor: &mut V) -> Result<(), DecodeError>
+    where V: Visitor<'a>
+{
+    visit_observed(parser, visitor, |_, _| {})
+}
+
+/// `visit`, handing every event (and `None` at the end of input) to
+/// `observe` before `visitor`. What the visitor refuses is left to
+/// `Parser::refuse`, so that the parser's modes apply to it.
+fn visit_observed<'a, V, F>(parser: &mut Parser<'a>,
+                            visitor: &mut V,
+                            mut observe: F)
+                            -> Result<(), DecodeError>
+    where V: Visitor<'a>,
+          F: FnMut(&mut Parser<'a>, Option<&Event<'a>>)
+{
+    while let Some(event) = parser.next_event()? {
+        observe(parser, Some(&event));
+        let result = match event {
+            Event::StartObject => visitor.start_object(),
+            Event::EndObject => visitor.end_object(),
+            Event::StartArray => visitor.start_array(),
+            Event::EndArray => visitor.end_array(),
+            Event::Key(k) => visitor.key(k),
+            Event::String(s) => visitor.string(s),
+            Event::Integer(i) => visitor.integer(i),
+            Event::Float(f) => visitor.float(f),
+            Event::Number(n) => visitor.number(Number::from_lexeme(&n)),
+            Event::Decimal(d) => visitor.decimal(d),
+            Event::Bool(b) => visitor.bool(b),
+            Event::Null => visitor.null(),
+        };
+        if let Err(kind) = result {
+            parser.refuse(kind)?;
+        }
+    }
+    observe(parser, None);
+    Ok(())
+}
+
+/// Builds the tree, handing every event (and `None` at the end of input) to
+/// `observe` before it is applied.
+pub fn build<'a, F>(parser: &mut Parser<'a>, observe: F) -> Result<JFObject, DecodeError>
+    where F: FnMut(&mut Parser<'a>, Option<&Event<'a>>)
+{
+    build_tree(parser, observe)
+}
+
+pub fn build_tree<'a, T, F>(parser: &mut Parser<'a>, observe: F) -> Result<T, DecodeError>
+    where T: Node<'a>,
+          F: FnMut(&mut Parser<'a>, Option<&Event<'a>>)
+{
+    let mut builder = JFObjectBuilder::<T>::for_parser(parser);
+    visit_observed(parser, &mut builder, observe)?;
+    Ok(builder.finish())
+}
+
+/// A tree that `JFObjectBuilder` can assemble.
+pub trait Tree: Sized {
+    type Key: Eq + Hash + AsRef<str>;
+
+    fn array() -> Self;
+    fn object() -> Self;
+    /// A value that is neither a string nor a container.
+    fn scalar(value: JFObject) -> Self;
+    fn as_vec_mut(&mut self) -> Option<&mut Vec<Self>>;
+    fn as_map_mut(&mut self) -> Option<&mut HashMap<Self::Key, Self>>;
+}
+
+/// A `Tree` whose keys and strings may borrow from text living for `'a`.
+pub trait Node<'a>: Tree {
+    fn key(name: Cow<'a, str>) -> Self::Key;
+    fn string(value: Cow<'a, str>) -> Self;
+}
+
+impl Tree for JFObject {
+    type Key = String;
+
+    fn array() -> JFObject {
+        JFObject::Array(Vec::new())
+    }
+    fn object() -> JFObject {
+        JFObject::Dictionary(HashMap::new())
+    }
+    fn scalar(value: JFObject) -> JFObject {
+        value
+    }
+    fn as_vec_mut(&mut self) -> Option<&mut Vec<JFObject>> {
+        match *self {
+            JFObject::Array(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+    fn as_map_mut(&mut self) -> Option<&mut HashMap<String, JFObject>> {
+        match *self {
+            JFObject::Dictionary(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Node<'a> for JFObject {
+    fn key(name: Cow<'a, str>) -> String {
+        name.into_owned()
+    }
+    fn string(value: Cow<'a, str>) -> JFObject {
+        JFObject::String(value.into_owned())
+    }
+}
+
+/// The visitor behind `decode_with` and the other decoders, building a
+/// `JFObject` and applying `Options::duplicate_keys`.
+pub struct JFObjectBuilder<T: Tree = JFObject> {
+    duplicate_keys: DuplicateKeys,
+    /// Whether to refuse every repeated key, rather than only under
+    /// `DuplicateKeys::Error`, for the parser to warn about.
+    refuse_repeated: bool,
+    root: Option<T>,
+    /// Open containers, with the key each will get in its parent and the
+    /// keys `DuplicateKeys::Collect` has turned into arrays.
+    stack: Vec<(T, Option<T::Key>, Vec<String>)>,
+    key: Option<T::Key>,
+}
+
+impl JFObjectBuilder {
+    pub fn new(options: &Options) -> JFObjectBuilder {
+        JFObjectBuilder::with(options.duplicate_keys, false)
+    }
+}
+
+impl<T: Tree> JFObjectBuilder<T> {
+    fn with(duplicate_keys: DuplicateKeys, refuse_repeated: bool) -> JFObjectBuilder<T> {
+        JFObjectBuilder {
+            duplicate_keys,
+            refuse_repeated: refuse_repeated || duplicate_keys == DuplicateKeys::Error,
+            root: None,
+            stack: Vec::new(),
+            key: None,
+        }
+    }
+
+    fn for_parser(parser: &Parser) -> JFObjectBuilder<T> {
+        JFObjectBuilder::with(parser.options().duplicate_keys, parser.reports_warnings())
+    }
+
+    fn open(&mut self, value: T) -> Result<(), ErrorKind> {
+        self.stack.push((value, self.key.take(), Vec::new()));
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), ErrorKind> {
+        let (value, key, _) = self.stack.pop().unwrap();
+        self.key = key;
+        self.add(value)
+    }
+
+    fn add(&mut self, value: T) -> Result<(), ErrorKind> {
+        let (open, collected) = match self.stack.last_mut() {
+            Some(&mut (ref mut open, _, ref mut collected)) => (open, collected),
+            None => {
+                self.root = Some(value);
+                return Ok(());
+            }
+        };
+        if let Some(v) = open.as_vec_mut() {
+            v.push(value);
+            return Ok(());
+        }
+        let map = open.as_map_mut().unwrap();
+        let mut slot = match map.entry(self.key.take().unwrap()) {
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                return Ok(());
+            }
+            Entry::Occupied(entry) => entry,
+        };
+        // Only reached for a key already refused, or let through by the
+        // policy; `DuplicateKeys::Error` then leaves the object as it was.
+        match self.duplicate_keys {
+            DuplicateKeys::LastWins => {
+                slot.insert(value);
+            }
+            DuplicateKeys::FirstWins | DuplicateKeys::Error => {}
+            DuplicateKeys::Collect => {
+                let name = slot.key().as_ref().to_owned();
+                if !collected.contains(&name) {
+                    let first = mem::replace(slot.get_mut(), T::array());
+                    slot.get_mut().as_vec_mut().unwrap().push(first);
+                    collected.push(name);
+                }
+                if let Some(v) = slot.get_mut().as_vec_mut() {
+                    v.push(value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Node<'a>> Visitor<'a> for JFObjectBuilder<T> {
+    type Output = T;
+
+    fn start_object(&mut self) -> Result<(), ErrorKind> {
+        self.open(T::object())
+    }
+    fn end_object(&mut self) -> Result<(), ErrorKind> {
+        self.close()
+    }
+    fn start_array(&mut self) -> Result<(), ErrorKind> {
+        self.open(T::array())
+    }
+    fn end_array(&mut self) -> Result<(), ErrorKind> {
+        self.close()
+    }
+    fn key(&mut self, key: Cow<'a, str>) -> Result<(), ErrorKind> {
+        let key = T::key(key);
+        // Refused here rather than when the value is added, so that the error
+        // points at the key. The key is kept for the value to be added under
+        // the policy if decoding carries on.
+        let repeated = match self.stack.last_mut() {
+            Some(&mut (ref mut open, _, _)) if self.refuse_repeated => {
+                open.as_map_mut().is_some_and(|map| map.contains_key(&key))
+            }
+            _ => false,
+        };
+        let name = if repeated { Some(key.as_ref().to_owned()) } else { None };
+        self.key = Some(key);
+        match name {
+            Some(name) => Err(ErrorKind::DuplicateKey(name)),
+            None => Ok(()),
+        }
+    }
+    fn string(&mut self, value: Cow<'a, str>) -> Result<(), ErrorKind> {
+        self.add(T::string(value))
+    }
+    fn integer(&mut self, value: i64) -> Result<(), ErrorKind> {
+        self.add(T::scalar(JFObject::Integer(value)))
+    }
+    fn float(&mut self, value: f64) -> Result<(), ErrorKind> {
+        self.add(T::scalar(JFObject::Float(value)))
+    }
+    fn number(&mut self, value: Number) -> Result<(), ErrorKind> {
+        self.add(T::scalar(JFObject::Number(value)))
+    }
+    fn decimal(&mut self, value: Decimal) -> Result<(), ErrorKind> {
+        self.add(T::scalar(JFObject::Decimal(value)))
+    }
+    fn bool(&mut self, value: bool) -> Result<(), ErrorKind> {
+        self.add(T::scalar(if value { JFObject::True } else { JFObject::False }))
+    }
+    fn null(&mut self) -> Result<(), ErrorKind> {
+        self.add(T::scalar(JFObject::Null))
+    }
+    fn finish(self) -> T {
+        self.root.unwrap_or_else(|| T::scalar(JFObject::Null))
+    }
+}